@@ -1,4 +1,6 @@
-use std::io::BufRead;
+use common::scanner::Scanner;
+use common::solver::{SolveError, Solver};
+use std::io::{BufRead, Write};
 
 // Solution to https://codeforces.com/contest/1672/problem/B
 
@@ -44,16 +46,27 @@ fn is_constructible(s : &str) -> bool {
         }
     }
 
-    return false;
+    false
 }
 
-fn main() {
-    let stdin = std::io::stdin();
-    for line in stdin.lock().lines().skip(1) {
-        println!("{}", if is_constructible(&line.unwrap()) { "YES"} else { "NO"});
+struct Solution;
+
+impl Solver for Solution {
+    fn solve<R: BufRead, W: Write>(&self, input: R, mut output: W) -> Result<(), SolveError> {
+        let mut scan = Scanner::new(input);
+        let t: usize = scan.next()?;
+        for _ in 0..t {
+            let s: String = scan.next()?;
+            writeln!(output, "{}", if is_constructible(&s) { "YES"} else { "NO"})?;
+        }
+        Ok(())
     }
 }
 
+fn main() -> Result<(), SolveError> {
+    Solution.run_stdio()
+}
+
 
 
 
@@ -66,4 +79,12 @@ fn test_1() {
     assert!(is_constructible("AAAAAAAAB"));
     assert!(is_constructible("ABABAB"));
     */
-}
\ No newline at end of file
+}
+
+#[test]
+fn samples() {
+    assert_eq!(
+        Solution.solve_bytes(b"4\nAABAB\nABB\nAAAAAAAAB\nA\n").unwrap(),
+        b"YES\nNO\nYES\nNO\n"
+    );
+}
@@ -2,14 +2,11 @@
 
 
 
-use std::collections::hash_map::IntoIter;
-use std::collections::HashMap;
-use std::convert::TryInto;
+use common::scanner::{ScanError, Scanner};
+use common::solver::{SolveError, Solver};
 use std::fmt;
-use std::io;
-use std::io::{stdin, BufRead, Read};
+use std::io::{BufRead, Write};
 use std::ops;
-use std::ops::Add;
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash)]
 struct FishSet(u32);
@@ -111,37 +108,6 @@ impl Iterator for FishSetIter {
     }
 }
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
-struct WinSetFish<SetBits, FishBits>(u32, SetBits, FishBits);
-
-impl<SetBits, FishBits> WinSetFish<SetBits, FishBits>
-    where
-        SetBits: U32 + Default,
-        FishBits: U32 + Default,
-{
-    fn pair(winner: Fish, looser: Fish) -> Self {
-        WinSetFish::new(winner, FishSet::pair(winner, looser))
-    }
-
-    fn new(fish: Fish, set: FishSet) -> Self {
-        let ones = (1 << FishBits::u32()) - 1;
-        WinSetFish(
-            set.0 << FishBits::u32() | fish.0 & ones,
-            SetBits::default(),
-            FishBits::default(),
-        )
-    }
-
-    fn fish(&self) -> Fish {
-        let ones = (1 << FishBits::u32()) - 1;
-        Fish(self.0 & ones)
-    }
-
-    fn set(&self) -> FishSet {
-        FishSet(self.0 >> FishBits::u32())
-    }
-}
-
 trait U32 {
     fn u32() -> u32;
 }
@@ -250,14 +216,14 @@ impl<SetBits, FishBits> WinProbabilityGeneric<SetBits, FishBits>
             })
             .sum::<Float>()
             / branch_count;
-        self.insert(target.clone(), result);
+        self.insert(target, result);
         result
     }
 }
 
 fn to_float(x: usize) -> Float {
     let x: u16 = x.try_into().unwrap();
-    x.try_into().unwrap()
+    x.into()
 }
 
 fn permutations(k: u32, n: u32) -> Vec<FishSet> {
@@ -266,7 +232,7 @@ fn permutations(k: u32, n: u32) -> Vec<FishSet> {
 
 fn _permutations(set: FishSet, k: u32, n: u32, start: u32) -> Vec<FishSet> {
     let mut arr = Vec::new();
-    for fish in (start..n).map(|i| Fish(i)) {
+    for fish in (start..n).map(Fish) {
         let new_set = set + fish;
         if new_set == set {
             continue;
@@ -297,62 +263,58 @@ fn prepare(memoized: &mut WinProbability, n: u32) {
     }
 }
 
-fn main() {
-    let mut buffer = String::new();
-    io::stdin().read_line(&mut buffer).expect("read n failed");
-    let n = buffer.trim().parse().expect("failed to parse n");
-    // let n = 4;
-    let memoized = WinProbability::new();
-    let mut memoized = read_probabilities(&mut io::stdin().lock(), &mut buffer, n, memoized);
-    prepare(&mut memoized, n);
-
-    // println!("{:?}", memoized);
-    for i in 0..n {
-        let member = Win::new(Fish(i), FishSet::new(n));
-        let probability = memoized.wins(member);
-        print!("{} ", fmt_float(probability));
+struct Solution;
+
+impl Solver for Solution {
+    fn solve<R: BufRead, W: Write>(&self, input: R, mut output: W) -> Result<(), SolveError> {
+        let mut scan = Scanner::new(input);
+        let n = scan.next()?;
+        // let n = 4;
+        let memoized = WinProbability::new();
+        let mut memoized = read_probabilities(&mut scan, n, memoized)?;
+        prepare(&mut memoized, n);
+
+        // println!("{:?}", memoized);
+        for i in 0..n {
+            let member = Win::new(Fish(i), FishSet::new(n));
+            let probability = memoized.wins(member);
+            write!(output, "{} ", fmt_float(probability))?;
+        }
+        // println!();
+        // println!("{:?}", memoized);
+        Ok(())
     }
-    // println!();
-    // println!("{:?}", memoized);
 }
 
-fn read_probabilities<S>(
-    stream: &mut S,
-    buffer: &mut String,
+fn main() -> Result<(), SolveError> {
+    Solution.run_stdio()
+}
+
+fn read_probabilities<R>(
+    scan: &mut Scanner<R>,
     n: u32,
     mut probabilities: WinProbability,
-) -> WinProbability
+) -> Result<WinProbability, ScanError>
     where
-        S: BufRead,
+        R: BufRead,
 {
     for i in 0..n {
-        buffer.clear();
-        stream
-            .read_line(buffer)
-            .expect(&format!("failed to read line {}", i));
-        let row = buffer.split(" ").map(|v| {
-            v.trim()
-                .parse::<Float>()
-                .expect(&format!("failed to parse value: {}", v))
-        });
-        for (j, probability) in row.enumerate() {
-            let j = j.try_into().unwrap();
+        for j in 0..n {
+            let probability = scan.next::<Float>()?;
             let win = Win::new(Fish(i), FishSet::empty() + Fish(i) + Fish(j));
             probabilities.insert(win, probability);
         }
     }
-    probabilities
+    Ok(probabilities)
 }
 
 fn fmt_float(x: Float) -> String {
-    return format!("{:.6}", x);
+    format!("{:.6}", x)
 }
 
+#[cfg(test)]
 mod tests {
     use super::*;
-    use std::collections::HashMap;
-    use std::convert::TryInto;
-    use std::num::NonZeroI32;
 
     #[test]
     fn permutations_2_3() {
@@ -426,7 +388,7 @@ mod tests {
 
     fn get_result<F>(n: u32, f: F) -> Vec<String>
         where
-            F: Fn(&mut WinProbability) -> (),
+            F: Fn(&mut WinProbability),
     {
         let mut proba = WinProbability::new();
         f(&mut proba);
@@ -463,7 +425,7 @@ mod tests {
 
     #[test]
     fn win_probability_zeros() {
-        let mut actual = get_result(3, |proba| {
+        let actual = get_result(3, |proba| {
             proba.insert(Win::pair(Fish(0), Fish(1)), 1.0);
             proba.insert(Win::pair(Fish(1), Fish(0)), 0.0);
             proba.insert(Win::pair(Fish(0), Fish(2)), 1.0);
@@ -489,4 +451,30 @@ mod tests {
         let expected: Vec<String> = (0..n).map(|_| fmt_float(1. / (n as Float))).collect();
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn samples() {
+        assert_eq!(
+            Solution.solve_bytes(b"2\n0 0.5\n0.5 0\n").unwrap(),
+            b"0.500000 0.500000 "
+        );
+        let input = b"5
+0 1 1 1 1
+0 0 0.5 0.5 0.5
+0 0.5 0 0.5 0.5
+0 0.5 0.5 0 0.5
+0 0.5 0.5 0.5 0
+";
+        assert_eq!(
+            Solution.solve_bytes(input).unwrap(),
+            b"1.000000 0.000000 0.000000 0.000000 0.000000 "
+        );
+    }
+
+    #[test]
+    fn malformed_input_is_an_error() {
+        for input in [&b"x\n"[..], b"2\n0 0.5\n0.5\n", b"2\n0 half\n0.5 0\n"] {
+            assert!(matches!(Solution.solve_bytes(input), Err(SolveError::Scan(_))));
+        }
+    }
 }
\ No newline at end of file
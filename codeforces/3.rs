@@ -1,4 +1,6 @@
-use std::io::BufRead;
+use common::scanner::Scanner;
+use common::solver::{SolveError, Solver};
+use std::io::{BufRead, Write};
 
 // https://codeforces.com/contest/1672/problem/B
  
@@ -22,23 +24,34 @@ fn is_constructible(s : &str) -> bool {
     let found = s.find('B');
     match found {
         Some(idx) if idx == s.len() -1 => { // here this means s is already a "good" string ,meaning a single op is needed
-            return true;
+            true
         },
         Some(0) => { // B is at the beginning , this is a trivial case of non-constructible structure
-            return false;
+            false
         },
-        None => { return false; }, // s is only 'A's
-        _ => { return true; }
+        None => { false}, // s is only 'A's
+        _ => { true}
     }
 }
  
  
-fn main() {
-    let stdin = std::io::stdin();
-    for line in stdin.lock().lines().skip(1) {
-        println!("{}", if is_constructible(&line.unwrap()) { "YES"} else { "NO"});
+struct Solution;
+
+impl Solver for Solution {
+    fn solve<R: BufRead, W: Write>(&self, input: R, mut output: W) -> Result<(), SolveError> {
+        let mut scan = Scanner::new(input);
+        let t: usize = scan.next()?;
+        for _ in 0..t {
+            let s: String = scan.next()?;
+            writeln!(output, "{}", if is_constructible(&s) { "YES"} else { "NO"})?;
+        }
+        Ok(())
     }
 }
+
+fn main() -> Result<(), SolveError> {
+    Solution.run_stdio()
+}
  
  
 #[test]
@@ -48,4 +61,68 @@ fn test_1() {
     assert!(!is_constructible("ABB"));
     assert!(is_constructible("AAAAAAAAB"));
     assert!(is_constructible("ABABAB"));
-}
\ No newline at end of file
+}
+
+#[test]
+fn samples() {
+    assert_eq!(
+        Solution.solve_bytes(b"4\nAABAB\nABB\nAAAAAAAAB\nA\n").unwrap(),
+        b"YES\nNO\nYES\nNO\n"
+    );
+}
+
+// the exhaustive search of 1.rs, as the oracle
+#[cfg(test)]
+mod brute {
+    use common::scanner::Scanner;
+    use common::solver::{SolveError, Solver};
+    use std::io::{BufRead, Write};
+
+    fn is_constructible(s: &str) -> bool {
+        if s.len() == 1 || !s.ends_with('B') {
+            return false;
+        }
+        // every prefix needs as many As as Bs
+        let mut balance = 0i32;
+        for c in s.chars() {
+            balance += if c == 'A' { 1 } else { -1 };
+            if balance < 0 {
+                return false;
+            }
+        }
+        let first_b = match s.find('B') {
+            Some(0) | None => return false,
+            Some(idx) if idx == s.len() - 1 => return true,
+            Some(idx) => idx,
+        };
+        // drop the first B with any A before it
+        (0..first_b).any(|pos| is_constructible(&format!("{}{}", &s[..pos], &s[first_b + 1..])))
+    }
+
+    pub struct Solution;
+
+    impl Solver for Solution {
+        fn solve<R: BufRead, W: Write>(&self, input: R, mut output: W) -> Result<(), SolveError> {
+            let mut scan = Scanner::new(input);
+            let t: usize = scan.next()?;
+            for _ in 0..t {
+                let s: String = scan.next()?;
+                writeln!(output, "{}", if is_constructible(&s) { "YES" } else { "NO" })?;
+            }
+            Ok(())
+        }
+    }
+}
+
+#[test]
+fn stress_against_brute_force() {
+    use tools::stress::{Rng, Stress};
+
+    let generator = |rng: &mut Rng| {
+        let s: String = (0..rng.range(1, 10)).map(|_| *rng.pick(&['A', 'A', 'B'])).collect();
+        format!("1\n{}\n", s)
+    };
+    if let Err(found) = Stress::new(generator, brute::Solution, Solution).run(1672, 3000) {
+        panic!("{}", found);
+    }
+}
@@ -1,49 +1,51 @@
-use std::io;
+use common::scanner::Scanner;
+use common::solver::{SolveError, Solver};
+use std::io::{BufRead, Write};
 
 // https://codeforces.com/contest/279/problem/B
  
-fn main() {
-    let mut line = String::new();
+struct Solution;
  
-    io::stdin()
-        .read_line(&mut line)
-        .unwrap();
+impl Solver for Solution {
+    fn solve<R: BufRead, W: Write>(&self, input: R, mut output: W) -> Result<(), SolveError> {
+        let mut scan = Scanner::new(input);
  
-    let words: Vec<i64> =
-        line
-        .split_whitespace()
-        .map(|x| x.parse().unwrap())
-        .collect();
+        let n: i64 = scan.next()?;
+        let t: i64 = scan.next()?;
  
-    let n = words[0];
-    let t = words[1];
+        let a: Vec<i64> = scan.vec(n as usize)?;
  
-    let mut line = String::new();
+        let mut prev: usize = 0;
  
-    io::stdin()
-        .read_line(&mut line)
-        .unwrap();
+        let mut sum = [0;100005];
+        let mut maxima = 0;
  
-    let a: Vec<i64> =
-        line
-        .split_whitespace()
-        .map(|x| x.parse().unwrap())
-        .collect();
+        for i in 1..=n {
+            sum[i as usize] = sum[(i-1) as usize] + a[(i-1) as usize];
  
-    let mut prev: usize = 0;
+            if sum[i as usize]-sum[prev] > t {
+                prev += 1;
+            }
  
-    let mut sum = [0;100005];
-    let mut maxima = 0;
- 
-    for i in 1..=n {
-        sum[i as usize] = sum[(i-1) as usize] + a[(i-1) as usize];
- 
-        if sum[i as usize]-sum[prev] > t {
-            prev += 1;
+            maxima = std::cmp::max(i-(prev as i64), maxima);
         }
  
-        maxima = std::cmp::max(i-(prev as i64), maxima);
+        writeln!(output, "{}", maxima)?;
+        Ok(())
     }
+}
+ 
+fn main() -> Result<(), SolveError> {
+    Solution.run_stdio()
+}
  
-    println!("{}", maxima);
-}
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use super::*;
+ 
+    #[test]
+    fn samples() {
+        assert_eq!(Solution.solve_bytes(b"4 5\n3 1 2 1\n").unwrap(), b"3\n");
+        assert_eq!(Solution.solve_bytes(b"3 3\n2 2 3\n").unwrap(), b"1\n");
+    }
+}
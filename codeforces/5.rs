@@ -1,32 +1,44 @@
 // https://codeforces.com/problemset/problem/466/A
-use std::io;
- 
-fn main() {
-    let mut line = String::new();
- 
-    io::stdin()
-        .read_line(&mut line)
-        .unwrap();
- 
-    let words: Vec<i64> =
-        line
-        .split_whitespace()
-        .map(|x| x.parse().unwrap())
-        .collect();
- 
-    let n = words[0];
-    let m = words[1];
-    let a = words[2];
-    let b = words[3];
- 
-    let sale: f64 = b as f64/m as f64;
- 
-    if sale < a as f64 {
-        let mut ans = (n as f64/m as f64).floor() as i64*b;
-        let minima = std::cmp::min((n%m)*a, b);
-        ans += minima;
-        println!("{}", ans);
-    } else {
-        println!("{}", n*a);
+use common::scanner::Scanner;
+use common::solver::{SolveError, Solver};
+use std::io::{BufRead, Write};
+ 
+struct Solution;
+ 
+impl Solver for Solution {
+    fn solve<R: BufRead, W: Write>(&self, input: R, mut output: W) -> Result<(), SolveError> {
+        let mut scan = Scanner::new(input);
+ 
+        let n: i64 = scan.next()?;
+        let m: i64 = scan.next()?;
+        let a: i64 = scan.next()?;
+        let b: i64 = scan.next()?;
+ 
+        let sale: f64 = b as f64/m as f64;
+ 
+        if sale < a as f64 {
+            let mut ans = (n as f64/m as f64).floor() as i64*b;
+            let minima = std::cmp::min((n%m)*a, b);
+            ans += minima;
+            writeln!(output, "{}", ans)?;
+        } else {
+            writeln!(output, "{}", n*a)?;
+        }
+        Ok(())
+    }
+}
+ 
+fn main() -> Result<(), SolveError> {
+    Solution.run_stdio()
+}
+ 
+#[cfg(test)]
+mod tests {
+    use super::*;
+ 
+    #[test]
+    fn samples() {
+        assert_eq!(Solution.solve_bytes(b"6 2 1 2\n").unwrap(), b"6\n");
+        assert_eq!(Solution.solve_bytes(b"5 2 2 3\n").unwrap(), b"8\n");
     }
-}
\ No newline at end of file
+}
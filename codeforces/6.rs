@@ -1,6 +1,8 @@
 // https://codeforces.com/problemset/problem/492/B
 // greedy, sorting
-use std::io;
+use common::scanner::Scanner;
+use common::solver::{SolveError, Solver};
+use std::io::{BufRead, Write};
  
 fn check(lanterns: &Vec<i64>, l: i64) -> f64 {
     let mut current = 0;
@@ -19,38 +21,38 @@ fn check(lanterns: &Vec<i64>, l: i64) -> f64 {
         maxima = distance;
     }
  
-    return maxima;
+    maxima
 }
  
-fn main() {
-    let mut line = String::new();
+struct Solution;
  
-    io::stdin()
-        .read_line(&mut line)
-        .unwrap();
+impl Solver for Solution {
+    fn solve<R: BufRead, W: Write>(&self, input: R, mut output: W) -> Result<(), SolveError> {
+        let mut scan = Scanner::new(input);
  
-    let words: Vec<i64> =
-        line
-        .split_whitespace()
-        .map(|x| x.parse().unwrap())
-        .collect();
+        let n: usize = scan.next()?;
+        let l: i64 = scan.next()?;
  
-    let _n = words[0];
-    let l = words[1];
+        let mut a: Vec<i64> = scan.vec(n)?;
  
-    let mut line = String::new();
+        a.sort();
  
-    io::stdin()
-        .read_line(&mut line)
-        .unwrap();
+        writeln!(output, "{}", check(&a, l))?;
+        Ok(())
+    }
+}
  
-    let mut a: Vec<i64> =
-        line
-        .split_whitespace()
-        .map(|x| x.parse().unwrap())
-        .collect();
+fn main() -> Result<(), SolveError> {
+    Solution.run_stdio()
+}
  
-    a.sort();
+#[cfg(test)]
+mod tests {
+    use super::*;
  
-    println!("{}", check(&a, l));
-}
\ No newline at end of file
+    #[test]
+    fn samples() {
+        assert_eq!(Solution.solve_bytes(b"7 15\n15 5 3 7 9 14 0\n").unwrap(), b"2.5\n");
+        assert_eq!(Solution.solve_bytes(b"2 5\n2 5\n").unwrap(), b"2\n");
+    }
+}
@@ -1,45 +1,51 @@
 
 // https://codeforces.com/problemset/problem/479/C
 // greedy
-use std::io;
+use common::scanner::Scanner;
+use common::solver::{SolveError, Solver};
+use std::io::{BufRead, Write};
  
-fn main() {
-    let mut n = String::new();
+struct Solution;
  
-    io::stdin()
-        .read_line(&mut n)
-        .unwrap();
+impl Solver for Solution {
+    fn solve<R: BufRead, W: Write>(&self, input: R, mut output: W) -> Result<(), SolveError> {
+        let mut scan = Scanner::new(input);
  
-    let n: i64 = n.trim().parse().unwrap();
+        let n: i64 = scan.next()?;
  
-    let mut exams = Vec::<Vec<i64>>::new();
+        let mut exams = Vec::<Vec<i64>>::new();
  
-    for _ in 0..n {
-        let mut line = String::new();
+        for _ in 0..n {
+            exams.push(scan.vec(2)?);
+        }
  
-        io::stdin()
-            .read_line(&mut line)
-            .unwrap();
+        exams.sort();
  
-        let words: Vec<i64> =
-            line
-            .split_whitespace()
-            .map(|x| x.parse().unwrap())
-            .collect();
+        let mut last = -1;
+        for exam in exams {
+            if exam[1] >= last {
+                last = exam[1];
+            } else {
+                last = exam[0];
+            }
+        }
  
-        exams.push(words);
+        writeln!(output, "{}", last)?;
+        Ok(())
     }
+}
  
-    exams.sort();
+fn main() -> Result<(), SolveError> {
+    Solution.run_stdio()
+}
  
-    let mut last = -1;
-    for exam in exams {
-        if exam[1] >= last {
-            last = exam[1];
-        } else {
-            last = exam[0];
-        }
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
  
-    println!("{}", last);
-}
\ No newline at end of file
+    #[test]
+    fn samples() {
+        assert_eq!(Solution.solve_bytes(b"3\n5 2\n3 1\n4 2\n").unwrap(), b"2\n");
+        assert_eq!(Solution.solve_bytes(b"3\n6 1\n5 2\n4 3\n").unwrap(), b"6\n");
+    }
+}
@@ -1,8 +1,10 @@
 // https://codeforces.com/problemset/problem/489/C
-use std::io;
+use common::scanner::Scanner;
+use common::solver::{SolveError, Solver};
+use std::io::{BufRead, Write};
 use std::char;
  
-fn reverse(cadena: &String) -> String {
+fn reverse(cadena: &str) -> String {
     let mut ans = String::from("");
     for ch in cadena.chars().rev() {
         ans.push(ch);
@@ -10,72 +12,81 @@ fn reverse(cadena: &String) -> String {
     ans
 }
  
-fn main() {
-    let mut line = String::new();
+struct Solution;
  
-    io::stdin()
-        .read_line(&mut line)
-        .unwrap();
+impl Solver for Solution {
+    fn solve<R: BufRead, W: Write>(&self, input: R, mut output: W) -> Result<(), SolveError> {
+        let mut scan = Scanner::new(input);
  
-    let words: Vec<i64> =
-        line
-        .split_whitespace()
-        .map(|x| x.parse().unwrap())
-        .collect();
+        let m: usize = scan.next()?;
+        let mut s: i64 = scan.next()?;
  
-    let m = words[0] as usize;
-    let mut s = words[1];
-    let mut _t = words[1];
- 
-    if s == 0 && m != 1 {
-        println!("-1 -1");
-        return;
-    }
+        if s == 0 && m != 1 {
+            writeln!(output, "-1 -1")?;
+            return Ok(());
+        }
  
-    if s == 0 && m == 1 {
-        println!("0 0");
-        return;
-    }
+        if s == 0 && m == 1 {
+            writeln!(output, "0 0")?;
+            return Ok(());
+        }
  
-    // lets construct maxima
-    let mut maxima = String::from("");
+        // lets construct maxima
+        let mut maxima = String::from("");
  
-    while s > 0 {
-        if s >= 9 {
-            maxima = format!("9{}", maxima);
-            s -= 9;
-        } else {
-            maxima = format!("{}{}", maxima, s);
-            s -= s;
+        while s > 0 {
+            if s >= 9 {
+                maxima = format!("9{}", maxima);
+                s -= 9;
+            } else {
+                maxima = format!("{}{}", maxima, s);
+                s -= s;
+            }
         }
-    }
     
-    if maxima.len() > m {
-        println!("-1 -1");
-        return;
-    }
+        if maxima.len() > m {
+            writeln!(output, "-1 -1")?;
+            return Ok(());
+        }
  
-    while maxima.len() < m {
-        maxima = format!("{}0", maxima);
-    }
+        while maxima.len() < m {
+            maxima = format!("{}0", maxima);
+        }
  
-    // lets construct minima
-    let mut chars: Vec<char> = reverse(&maxima).chars().collect();
-    let mut minima = String::from("");
+        // lets construct minima
+        let mut chars: Vec<char> = reverse(&maxima).chars().collect();
+        let mut minima = String::from("");
  
-    if chars[0] == '0' {
-        chars[0] = '1';
-        for i in 1..chars.len() {
-            if chars[i] != '0' {
-                chars[i] = char::from_digit(chars[i].to_digit(10).unwrap()-1,10).unwrap();
-                break;
+        if chars[0] == '0' {
+            chars[0] = '1';
+            for ch in chars.iter_mut().skip(1) {
+                if *ch != '0' {
+                    *ch = char::from_digit(ch.to_digit(10).unwrap()-1,10).unwrap();
+                    break;
+                }
             }
         }
-    }
  
-    for ch in chars {
-        minima.push(ch);
+        for ch in chars {
+            minima.push(ch);
+        }
+ 
+        writeln!(output, "{} {}", minima, maxima)?;
+        Ok(())
     }
+}
  
-    println!("{} {}", minima, maxima);
-}
\ No newline at end of file
+fn main() -> Result<(), SolveError> {
+    Solution.run_stdio()
+}
+ 
+#[cfg(test)]
+mod tests {
+    use super::*;
+ 
+    #[test]
+    fn samples() {
+        assert_eq!(Solution.solve_bytes(b"2 15\n").unwrap(), b"69 96\n");
+        assert_eq!(Solution.solve_bytes(b"3 0\n").unwrap(), b"-1 -1\n");
+    }
+}
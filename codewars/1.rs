@@ -1,70 +1,205 @@
-/// Here is an implementation of https://www.codewars.com/kata/577e9095d648a15b800000d4/discuss
+// Here is an implementation of https://www.codewars.com/kata/577e9095d648a15b800000d4/discuss
 
-use std::collections::VecDeque;
+use crate::formula::Formula;
+use crate::numeric::Number;
+use crate::registry::{Body, Function, Registry, Word};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
 
-enum Op {
-    Substract,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Subtract,
     Add,
     Multiply,
-    Divide
+    Divide,
+    Remainder,
+    Power
 }
 
-enum ExprNode {
-    Value(i32),
-    Operator(Op)
+impl Op {
+    pub const ALL: [Op; 6] = [Op::Subtract, Op::Add, Op::Multiply, Op::Divide, Op::Remainder, Op::Power];
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Op::Subtract => "-",
+            Op::Add => "+",
+            Op::Multiply => "*",
+            Op::Divide => "/",
+            Op::Remainder => "%",
+            Op::Power => "^",
+        }
+    }
+
+    pub fn from_symbol(s: &str) -> Option<Op> {
+        Op::ALL.into_iter().find(|op| op.symbol() == s)
+    }
+
+    pub fn apply<N: Number>(self, lhs: &N, rhs: &N) -> Result<N, ErrorKind> {
+        match self {
+            Op::Subtract => lhs.sub(rhs),
+            Op::Add => lhs.add(rhs),
+            Op::Multiply => lhs.mul(rhs),
+            Op::Divide => lhs.div(rhs),
+            Op::Remainder => lhs.rem(rhs),
+            Op::Power => lhs.pow(rhs),
+        }
+    }
 }
 
-impl ExprNode {
-    fn from_str(s: &str) -> Self {
-        if let Ok(n) = s.parse::<i32>() {
-            ExprNode::Value(n)
+/// Operators taking a single operand, written as a word since `-` is taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Negate
+}
+
+impl UnaryOp {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            UnaryOp::Negate => "neg",
         }
-        else {
-            match s {
-                "-" => ExprNode::Operator(Op::Substract),
-                "+" => ExprNode::Operator(Op::Add),
-                "*" => ExprNode::Operator(Op::Multiply),
-                "/" => ExprNode::Operator(Op::Divide),
-                _ => panic!("not supported yet")
-            }
+    }
+
+    pub fn from_symbol(s: &str) -> Option<UnaryOp> {
+        (s == "neg").then_some(UnaryOp::Negate)
+    }
+
+    pub fn apply<N: Number>(self, operand: &N) -> Result<N, ErrorKind> {
+        match self {
+            UnaryOp::Negate => operand.neg(),
         }
     }
 }
 
-fn postfix_evaluator(expr: &str) -> i64 {
-    let mut stack = VecDeque::from([]);
-
-    for node  in expr.split_whitespace().map(|s| ExprNode::from_str(s)){
-        match node {
-            ExprNode::Value(v) => stack.push_back(v as i64),
-            ExprNode::Operator(op) => {
-                let a = stack.pop_back().unwrap();
-                let b = stack.pop_back().unwrap();
-                match op {
-                    Op::Substract => {
-                        stack.push_back(a - b);
-                    },
-                    Op::Add => {
-                        stack.push_back(a + b);
-                    },
-                    Op::Multiply => {
-                        stack.push_back(a * b);
-                    },
-                    Op::Divide => {
-                        stack.push_back(b / a);
-                    },
-                }
-            },
+/// A token of any notation: a number or a word of the registry.
+pub(crate) enum ExprNode<'r, N> {
+    Value(N),
+    Word(&'r Word<N>)
+}
+
+impl<'r, N: Number> ExprNode<'r, N> {
+    pub(crate) fn from_str(s: &str, registry: &'r Registry<N>) -> Result<Self, ErrorKind> {
+        match registry.get(s) {
+            Some(word) => Ok(ExprNode::Word(word)),
+            None => N::parse(s).map(ExprNode::Value)
+        }
+    }
+
+    /// The function of a word, stack words being an error outside postfix.
+    pub(crate) fn function(word: &'r Word<N>, s: &str) -> Result<&'r Function<N>, ErrorKind> {
+        match &word.body {
+            Body::Function(function) => Ok(function),
+            Body::Shuffle(_) => Err(ErrorKind::StackWord(s.to_string()))
+        }
+    }
+}
+
+/// What is wrong with an expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// Neither a number nor a supported operator.
+    UnknownToken(String),
+    /// An operator found fewer operands than it takes, or the expression is
+    /// empty.
+    StackUnderflow,
+    /// Operands left over once the expression is consumed, how many.
+    LeftoverOperands(usize),
+    DivisionByZero,
+    /// A literal or an intermediate result does not fit in an i64.
+    Overflow,
+    /// An identifier the environment has no value for.
+    UnboundVariable(String),
+    /// A stack word such as `dup` outside postfix, which has no stack to
+    /// shuffle.
+    StackWord(String),
+    /// An operand the operation is not defined for, such as a negative
+    /// exponent or the gcd of fractions.
+    Domain(&'static str),
+}
+
+/// A failed evaluation, located at the offending token: its 0-based index
+/// among the tokens and its byte offset in the expression. Errors about the
+/// end of the expression point just past its last token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvalError {
+    pub kind: ErrorKind,
+    pub token: usize,
+    pub offset: usize,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::UnknownToken(t) => write!(f, "unknown token `{}`", t),
+            ErrorKind::StackUnderflow => f.write_str("not enough operands"),
+            ErrorKind::LeftoverOperands(n) => write!(f, "{} operand(s) left over", n),
+            ErrorKind::DivisionByZero => f.write_str("division by zero"),
+            ErrorKind::Overflow => f.write_str("integer overflow"),
+            ErrorKind::UnboundVariable(name) => write!(f, "`{}` is not bound", name),
+            ErrorKind::StackWord(name) => write!(f, "`{}` is a stack word, only postfix has a stack", name),
+            ErrorKind::Domain(reason) => f.write_str(reason),
         }
     }
-    stack.pop_back().unwrap()
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "token {} (byte {}): {}", self.token, self.offset, self.kind)
+    }
+}
+
+impl Error for EvalError {}
+
+/// Whitespace separated tokens with their byte offsets.
+pub fn tokens(expr: &str) -> impl Iterator<Item = (usize, &str)> {
+    expr.split_whitespace().map(move |token| (token.as_ptr() as usize - expr.as_ptr() as usize, token))
+}
+
+/// `width` carets under the text following `before`, the start of its line.
+/// Tabs are kept, so that the carets line up however tabs are shown.
+pub(crate) fn underline(before: &str, width: usize) -> String {
+    let padding: String = before.chars().map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+    padding + &"^".repeat(width)
+}
+
+/// The kata entry point, panics on a malformed expression.
+pub fn postfix_evaluator(expr: &str) -> i64 {
+    match try_postfix_evaluator(expr) {
+        Ok(value) => value,
+        Err(e) => panic!("{}", e)
+    }
+}
 
+thread_local! {
+    static STANDARD: Registry<i64> = Registry::standard();
+}
+
+/// Runs `f` with the standard `i64` words, built once per thread.
+pub(crate) fn with_standard<T>(f: impl FnOnce(&Registry<i64>) -> T) -> T {
+    STANDARD.with(f)
+}
+
+pub fn try_postfix_evaluator(expr: &str) -> Result<i64, EvalError> {
+    with_standard(|registry| evaluate_with(expr, registry))
+}
+
+/// Evaluates with any [`Number`] type, `evaluate::<Rational>("3 4 9 / *")` is
+/// exactly 4/3 where the kata's `i64` gives 0.
+pub fn evaluate<N: Number>(expr: &str) -> Result<N, EvalError> {
+    evaluate_with(expr, &Registry::standard())
+}
+
+/// Evaluates with the words of `registry`, any other token must be a number.
+/// Identifiers parse but are bound to nothing, [`Formula`] evaluates those.
+pub fn evaluate_with<N: Number>(expr: &str, registry: &Registry<N>) -> Result<N, EvalError> {
+    Formula::parse(expr, registry)?.eval(&mut HashMap::new())
 }
 
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tools::stress::Rng;
 
     #[test]
     fn basic() {
@@ -86,4 +221,135 @@ mod tests {
         // Multi-digit
         assert_eq!(postfix_evaluator("21 21 +"), 42);
     }
+
+    #[test]
+    fn operand_order() {
+        assert_eq!(postfix_evaluator("5 3 -"), 2);
+        assert_eq!(postfix_evaluator("3 5 -"), -2);
+        assert_eq!(postfix_evaluator("8 2 /"), 4);
+        assert_eq!(postfix_evaluator("10 2 8 * + 3 -"), 23);
+        assert_eq!(postfix_evaluator("2 3 neg *"), -6);
+    }
+
+    /// Reference evaluator for fully parenthesized infix with the usual
+    /// precedence, written independently of the stack machine above.
+    struct Infix<'a> {
+        s: &'a [u8],
+        pos: usize,
+    }
+
+    impl Infix<'_> {
+        fn peek(&mut self) -> Option<u8> {
+            while self.s.get(self.pos) == Some(&b' ') {
+                self.pos += 1;
+            }
+            self.s.get(self.pos).copied()
+        }
+
+        /// `None` on division by zero or overflow.
+        fn expr(&mut self) -> Option<i64> {
+            let mut value = self.term()?;
+            while let Some(op @ (b'+' | b'-')) = self.peek() {
+                self.pos += 1;
+                let rhs = self.term()?;
+                value = if op == b'+' { value.checked_add(rhs)? } else { value.checked_sub(rhs)? };
+            }
+            Some(value)
+        }
+
+        fn term(&mut self) -> Option<i64> {
+            let mut value = self.factor()?;
+            while let Some(op @ (b'*' | b'/')) = self.peek() {
+                self.pos += 1;
+                let rhs = self.factor()?;
+                value = if op == b'*' { value.checked_mul(rhs)? } else { value.checked_div(rhs)? };
+            }
+            Some(value)
+        }
+
+        fn factor(&mut self) -> Option<i64> {
+            match self.peek()? {
+                b'(' => {
+                    self.pos += 1;
+                    let value = self.expr()?;
+                    assert_eq!(self.peek(), Some(b')'));
+                    self.pos += 1;
+                    Some(value)
+                }
+                b'-' => {
+                    self.pos += 1;
+                    self.factor()?.checked_neg()
+                }
+                _ => {
+                    let start = self.pos;
+                    while self.s.get(self.pos).is_some_and(u8::is_ascii_digit) {
+                        self.pos += 1;
+                    }
+                    Some(std::str::from_utf8(&self.s[start..self.pos]).unwrap().parse().unwrap())
+                }
+            }
+        }
+    }
+
+    /// Random expression rendered as (postfix, infix).
+    fn random_expr(rng: &mut Rng, depth: u32) -> (String, String) {
+        if depth == 0 || rng.range(0, 3) == 0 {
+            let n = rng.range(-30, 30);
+            return (n.to_string(), n.to_string());
+        }
+        let op = *rng.pick(&["+", "-", "*", "/"]);
+        let (left_postfix, left_infix) = random_expr(rng, depth - 1);
+        let (right_postfix, right_infix) = random_expr(rng, depth - 1);
+        (
+            format!("{} {} {}", left_postfix, right_postfix, op),
+            format!("({} {} {})", left_infix, op, right_infix),
+        )
+    }
+
+    #[test]
+    fn agrees_with_an_infix_evaluator() {
+        let mut rng = Rng::new(577);
+        for _ in 0..5000 {
+            let (postfix, infix) = random_expr(&mut rng, 6);
+            let expected = Infix { s: infix.as_bytes(), pos: 0 }.expr();
+            assert_eq!(try_postfix_evaluator(&postfix).ok(), expected, "{} = {}", postfix, infix);
+        }
+    }
+
+    #[test]
+    fn exact_backends() {
+        use crate::bigint::BigInt;
+        use crate::numeric::Rational;
+
+        assert_eq!(postfix_evaluator("3 4 9 / *"), 0);
+        assert_eq!(evaluate::<Rational>("3 4 9 / *"), Ok(Rational::new(4, 3).unwrap()));
+        assert_eq!(evaluate::<Rational>("0.5 0.25 +").unwrap().to_string(), "3/4");
+        assert_eq!(evaluate::<f64>("3 4 9 / *").map(|x| (x * 3.).round()), Ok(4.));
+        let big = "9223372036854775807 9223372036854775807 * 9223372036854775807 /";
+        assert_eq!(error(big).0, ErrorKind::Overflow);
+        assert_eq!(evaluate::<BigInt>(big).unwrap().to_string(), "9223372036854775807");
+        assert_eq!(evaluate::<BigInt>("1 0 /").unwrap_err().kind, ErrorKind::DivisionByZero);
+    }
+
+    fn error(expr: &str) -> (ErrorKind, usize, usize) {
+        let e = try_postfix_evaluator(expr).unwrap_err();
+        (e.kind, e.token, e.offset)
+    }
+
+    #[test]
+    fn errors_are_located() {
+        assert_eq!(error("2 3 &"), (ErrorKind::UnknownToken("&".to_string()), 2, 4));
+        assert_eq!(error("2  +"), (ErrorKind::StackUnderflow, 1, 3));
+        assert_eq!(error(""), (ErrorKind::StackUnderflow, 0, 0));
+        assert_eq!(error("  "), (ErrorKind::StackUnderflow, 0, 2));
+        assert_eq!(error("1 2 3 +"), (ErrorKind::LeftoverOperands(1), 0, 0));
+        assert_eq!(error("1 2 3"), (ErrorKind::LeftoverOperands(2), 0, 0));
+        assert_eq!(error("4 1 1 - /"), (ErrorKind::DivisionByZero, 4, 8));
+        assert_eq!(error("99999999999999999999 1 +"), (ErrorKind::Overflow, 0, 0));
+        assert_eq!(error("9223372036854775807 1 +"), (ErrorKind::Overflow, 2, 22));
+        assert_eq!(
+            try_postfix_evaluator("1 0 /").unwrap_err().to_string(),
+            "token 2 (byte 4): division by zero"
+        );
+    }
 }
@@ -1,108 +1,483 @@
+use crate::postfix::underline;
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::fmt;
+use std::num::IntErrorKind;
+use std::ops::Range;
 
 // https://www.codewars.com/kata/58e24788e24ddee28e000053
+// and part II, https://www.codewars.com/kata/58e61f3d8ff24f774400002c
 
 // either variable name or a value
+#[derive(Clone)]
 enum Operand {
     Value(i64),
     Variable(String),
 }
+
 impl Operand {
-    fn from_string(s: &str) -> Self {
-        if let Ok(numb) = s.parse::<i64>() {
-            Operand::Value(numb)
+    fn parse(s: &str, register_only: bool) -> Result<Self, String> {
+        if is_register(s) {
+            return Ok(Operand::Variable(s.to_string()));
         }
-        else {
-            Operand::Variable(s.to_string())
+        let expected = if register_only { "a register" } else { "a register or an integer" };
+        match s.parse::<i64>() {
+            Ok(numb) if !register_only => Ok(Operand::Value(numb)),
+            Err(e) if !register_only && matches!(e.kind(), IntErrorKind::PosOverflow | IntErrorKind::NegOverflow) => {
+                Err(format!("`{}` does not fit in 64 bits", s))
+            },
+            _ => Err(format!("expected {}, found `{}`", expected, s))
+        }
+    }
+}
+
+// registers and labels alike
+fn is_register(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+// words separated by whitespace or commas with their byte range in the line,
+// a quoted string is one word and `;` outside of one starts a comment;
+// an unterminated string is an error spanning the rest of the line
+fn words(line: &str) -> Result<Vec<(Range<usize>, &str)>, Range<usize>> {
+    let separator = |c: char| c.is_whitespace() || c == ',';
+    let mut words = Vec::new();
+    let mut chars = line.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c == ';' {
+            break;
+        }
+        if separator(c) {
+            continue;
         }
+        let end = if c == '\'' {
+            match chars.find(|&(_, c)| c == '\'') {
+                Some((close, _)) => close + 1,
+                None => return Err(start..line.trim_end().len()),
+            }
+        }
+        else {
+            loop {
+                match chars.peek() {
+                    Some(&(i, c)) if separator(c) || c == ';' || c == '\'' => break i,
+                    Some(_) => { chars.next(); },
+                    None => break line.len(),
+                }
+            }
+        };
+        words.push((start..end, &line[start..end]));
     }
+    Ok(words)
+}
+
+/// A problem with one line of a program: `line` counts from 1, `span` is the
+/// byte range of the offending text within that line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub span: Range<usize>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// The message over `source`, the line it is about, with the span
+    /// underlined.
+    pub fn render(&self, source: &str) -> String {
+        let number = self.line.to_string();
+        let gutter = " ".repeat(number.len());
+        let start = self.span.start.min(source.len());
+        let end = self.span.end.clamp(start, source.len());
+        let width = source[start..end].chars().count().max(1);
+        format!(
+            "error: {}\n{} |\n{} | {}\n{} | {}",
+            self.message, gutter, number, source.trim_end(), gutter, underline(&source[..start], width)
+        )
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.span.start + 1, self.message)
+    }
+}
+
+/// What an operand of an instruction may be.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Register,
+    Value,
+    Label,
+}
+
+// a parsed operand, labels resolved to the index of their instruction
+enum Arg {
+    Operand(Operand),
+    Target(usize),
+}
+
+// a piece of a `msg`
+enum Part {
+    Text(String),
+    Value(Operand),
 }
 
 enum Instruction {
     Mov(String, Operand),
     Inc(String),
     Dec(String),
+    // add, sub and mul, wrapping on overflow
+    Arith(fn(i64, i64) -> i64, String, Operand),
+    Div(String, Operand),
     Jnz(Operand, Operand),
+    Cmp(Operand, Operand),
+    // unconditional for `None`, otherwise taken when the last `cmp` agrees
+    Jump(Option<fn(Ordering) -> bool>, usize),
+    Call(usize),
+    Ret,
+    Msg(Vec<Part>),
+    End,
 }
 
 impl Instruction {
-    fn from_string(s: &str) -> Self {
-        let exp: Vec<_> = s.split_whitespace().collect();
-        if exp.len() < 2 {
-            panic!("parse error");
+    /// What each operand must be, `None` for an unknown name. `msg` takes
+    /// any number of strings and values and is parsed on its own.
+    fn signature(name: &str) -> Option<&'static [Kind]> {
+        match name {
+            "mov" | "add" | "sub" | "mul" | "div" => Some(&[Kind::Register, Kind::Value]),
+            "inc" | "dec" => Some(&[Kind::Register]),
+            "jnz" | "cmp" => Some(&[Kind::Value, Kind::Value]),
+            "jmp" | "jne" | "je" | "jge" | "jg" | "jle" | "jl" | "call" => Some(&[Kind::Label]),
+            "ret" | "end" => Some(&[]),
+            _ => None
         }
-        else {
-            match exp[0] {
-                "mov" => Instruction::Mov(exp[1].to_string(), Operand::from_string(exp[2])),
-                "inc" => Instruction::Inc(exp[1].to_string()),
-                "dec" => Instruction::Dec(exp[1].to_string()),
-                "jnz" => Instruction::Jnz(Operand::from_string(exp[1]), Operand::from_string(exp[2])),
-                _ => panic!("parse error!")
+    }
+
+    /// Parses the `words` of line `number`, a label already taken off,
+    /// reporting every malformed part of it.
+    fn parse(number: usize, line: &str, words: &[(Range<usize>, &str)], labels: &HashMap<&str, usize>) -> Result<Self, Vec<Diagnostic>> {
+        let diagnostic = |span: Range<usize>, message: String| Diagnostic { line: number, span, message };
+        let (name_span, name) = words[0].clone();
+        let args = &words[1..];
+        if name == "msg" {
+            return Instruction::parse_msg(name_span.end, args).map_err(|errors| {
+                errors.into_iter().map(|(span, message)| diagnostic(span, message)).collect()
+            });
+        }
+        let Some(signature) = Instruction::signature(name) else {
+            return Err(vec![diagnostic(name_span, format!("unknown instruction `{}`", name))]);
+        };
+
+        let mut diagnostics = Vec::new();
+        let mut operands = Vec::new();
+        for (&kind, (span, arg)) in signature.iter().zip(args) {
+            let parsed = match kind {
+                Kind::Label if labels.contains_key(arg) => Ok(Arg::Target(labels[arg])),
+                Kind::Label if is_register(arg) => Err(format!("unknown label `{}`", arg)),
+                Kind::Label => Err(format!("expected a label, found `{}`", arg)),
+                _ => Operand::parse(arg, kind == Kind::Register).map(Arg::Operand),
+            };
+            match parsed {
+                Ok(operand) => operands.push(operand),
+                Err(message) => diagnostics.push(diagnostic(span.clone(), message)),
             }
         }
+        if args.len() < signature.len() {
+            let end = words.last().unwrap().0.end;
+            diagnostics.push(diagnostic(end..end, format!(
+                "`{}` takes {} operand(s), found {}", name, signature.len(), args.len()
+            )));
+        }
+        else if let Some((first, _)) = args.get(signature.len()) {
+            let end = args.last().unwrap().0.end;
+            diagnostics.push(diagnostic(first.start..end, format!(
+                "unexpected `{}` after the operands of `{}`", &line[first.start..end], name
+            )));
+        }
+        if !diagnostics.is_empty() {
+            return Err(diagnostics);
+        }
+
+        use Arg::{Operand as Op, Target};
+        let arith: fn(i64, i64) -> i64 = match name {
+            "add" => i64::wrapping_add,
+            "sub" => i64::wrapping_sub,
+            _ => i64::wrapping_mul,
+        };
+        let condition: fn(Ordering) -> bool = match name {
+            "jne" => Ordering::is_ne,
+            "je" => Ordering::is_eq,
+            "jge" => Ordering::is_ge,
+            "jg" => Ordering::is_gt,
+            "jle" => Ordering::is_le,
+            _ => Ordering::is_lt,
+        };
+        Ok(match (name, &operands[..]) {
+            ("mov", [Op(Operand::Variable(register)), Op(value)]) => Instruction::Mov(register.clone(), value.clone()),
+            ("inc", [Op(Operand::Variable(register))]) => Instruction::Inc(register.clone()),
+            ("dec", [Op(Operand::Variable(register))]) => Instruction::Dec(register.clone()),
+            ("add" | "sub" | "mul", [Op(Operand::Variable(register)), Op(value)]) => Instruction::Arith(arith, register.clone(), value.clone()),
+            ("div", [Op(Operand::Variable(register)), Op(value)]) => Instruction::Div(register.clone(), value.clone()),
+            ("jnz", [Op(value), Op(jump)]) => Instruction::Jnz(value.clone(), jump.clone()),
+            ("cmp", [Op(x), Op(y)]) => Instruction::Cmp(x.clone(), y.clone()),
+            ("jmp", [Target(target)]) => Instruction::Jump(None, *target),
+            ("call", [Target(target)]) => Instruction::Call(*target),
+            (_, [Target(target)]) => Instruction::Jump(Some(condition), *target),
+            ("ret", []) => Instruction::Ret,
+            ("end", []) => Instruction::End,
+            _ => unreachable!("operands checked against the signature"),
+        })
+    }
+
+    // `msg` operands are quoted strings, registers or integers; `end` is
+    // where the name ends, for a `msg` without any
+    fn parse_msg(end: usize, args: &[(Range<usize>, &str)]) -> Result<Self, Vec<(Range<usize>, String)>> {
+        if args.is_empty() {
+            return Err(vec![(end..end, "`msg` takes 1 or more operand(s), found 0".to_string())]);
+        }
+        let mut errors = Vec::new();
+        let mut parts = Vec::new();
+        for (span, arg) in args {
+            if let Some(text) = arg.strip_prefix('\'') {
+                parts.push(Part::Text(text[..text.len() - 1].to_string()));
+                continue;
+            }
+            match Operand::parse(arg, false) {
+                Ok(operand) => parts.push(Part::Value(operand)),
+                Err(message) => errors.push((span.clone(), message)),
+            }
+        }
+        if errors.is_empty() { Ok(Instruction::Msg(parts)) } else { Err(errors) }
+    }
+}
+
+/// How a run stopped. The faults carry the line, counting from 1, of the
+/// instruction that could not be executed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// `end` was reached, with everything `msg` produced.
+    Ended(String),
+    /// The program ran past its last instruction without an `end`.
+    NoEnd,
+    /// A register was used before any `mov` to it.
+    UnsetRegister { line: usize, register: String },
+    DivisionByZero { line: usize },
+    /// `ret` with no `call` to return to.
+    ReturnWithoutCall { line: usize },
+    /// `jnz` jumped before the first instruction.
+    JumpBeforeStart { line: usize },
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Ended(output) => f.write_str(output),
+            Outcome::NoEnd => f.write_str("program ended without `end`"),
+            Outcome::UnsetRegister { line, register } => write!(f, "line {}: register `{}` is not set", line, register),
+            Outcome::DivisionByZero { line } => write!(f, "line {}: division by zero", line),
+            Outcome::ReturnWithoutCall { line } => write!(f, "line {}: `ret` outside of a call", line),
+            Outcome::JumpBeforeStart { line } => write!(f, "line {}: jump before the first instruction", line),
+        }
     }
 }
 
 pub struct Program {
     pub registry: HashMap<String, i64>,
-    instructions: Vec<Instruction>
+    // with their line number
+    instructions: Vec<(usize, Instruction)>
 }
 
 impl Program {
+    /// The kata entry point, panics with the rendered diagnostics on a
+    /// malformed program.
     pub fn new(instructions: Vec<&str>) -> Self {
-        Self {
-            registry: Default::default(),
-            instructions: instructions.iter().map(|&s| Instruction::from_string(s)).collect()
+        match Self::parse(&instructions) {
+            Ok(program) => program,
+            Err(diagnostics) => {
+                let rendered: Vec<String> = diagnostics.iter().map(|d| d.render(instructions[d.line - 1])).collect();
+                panic!("{}", rendered.join("\n"))
+            }
+        }
+    }
+
+    /// At most one instruction per line, which may start with a `name:`
+    /// label; blank lines and comments are skipped. Every error of every
+    /// line is reported.
+    pub fn parse(lines: &[&str]) -> Result<Self, Vec<Diagnostic>> {
+        let mut diagnostics = Vec::new();
+        // labels first, jumps may go forward
+        let mut labels = HashMap::new();
+        let mut statements = Vec::new();
+        for (index, line) in lines.iter().enumerate() {
+            let number = index + 1;
+            let mut words = match words(line) {
+                Ok(words) => words,
+                Err(span) => {
+                    diagnostics.push(Diagnostic { line: number, span, message: "unterminated string".to_string() });
+                    continue;
+                }
+            };
+            if let Some((span, label)) = words.first().and_then(|(span, word)| Some((span.clone(), word.strip_suffix(':')?))) {
+                if !is_register(label) {
+                    diagnostics.push(Diagnostic { line: number, span, message: format!("expected a label, found `{}`", label) });
+                }
+                else if labels.insert(label, statements.len()).is_some() {
+                    diagnostics.push(Diagnostic { line: number, span, message: format!("label `{}` is defined twice", label) });
+                }
+                words.remove(0);
+            }
+            if !words.is_empty() {
+                statements.push((number, *line, words));
+            }
+        }
+
+        let mut instructions = Vec::new();
+        for (number, line, words) in &statements {
+            match Instruction::parse(*number, line, words, &labels) {
+                Ok(instruction) => instructions.push((*number, instruction)),
+                Err(found) => diagnostics.extend(found),
+            }
+        }
+        if diagnostics.is_empty() {
+            Ok(Self { registry: Default::default(), instructions })
+        }
+        else {
+            diagnostics.sort_by_key(|d| (d.line, d.span.start));
+            Err(diagnostics)
         }
     }
 
-    fn resolve_value(&self, op: &Operand) -> i64 {
+    /// A whole source text, see [`Program::parse`].
+    pub fn from_source(source: &str) -> Result<Self, Vec<Diagnostic>> {
+        Self::parse(&source.lines().collect::<Vec<_>>())
+    }
+
+    fn resolve_value(&self, op: &Operand, line: usize) -> Result<i64, Outcome> {
         match op {
-            Operand::Value(n) => *n,
-            Operand::Variable(var_name) => *self.registry.get(var_name).unwrap(),
+            Operand::Value(n) => Ok(*n),
+            Operand::Variable(var_name) => self.registry.get(var_name).copied().ok_or_else(|| {
+                Outcome::UnsetRegister { line, register: var_name.clone() }
+            }),
         }
     }
 
-    pub fn run(&mut self) {
+    fn register<'a>(registry: &'a mut HashMap<String, i64>, name: &str, line: usize) -> Result<&'a mut i64, Outcome> {
+        registry.get_mut(name).ok_or_else(|| Outcome::UnsetRegister { line, register: name.to_string() })
+    }
+
+    /// Runs from the first instruction, leaving the registers in `registry`.
+    pub fn run(&mut self) -> Outcome {
         // clear previous program state
         self.registry.clear();
 
+        match self.execute() {
+            Ok(output) => Outcome::Ended(output),
+            Err(outcome) => outcome,
+        }
+    }
+
+    // the output once `end` is reached, any other way of stopping as `Err`
+    fn execute(&mut self) -> Result<String, Outcome> {
         let mut instruction_index = 0;
+        let mut returns = Vec::new();
+        let mut comparison = None;
+        let mut output = String::new();
 
         while instruction_index < self.instructions.len() {
-            match &self.instructions[instruction_index] {
+            let (line, instruction) = &self.instructions[instruction_index];
+            let line = *line;
+            match instruction {
                 Instruction::Mov(variable, value) => {
-                    let val = self.resolve_value(value);
+                    let val = self.resolve_value(value, line)?;
                     // upsert
                     *self.registry.entry(variable.clone()).or_default() = val;
                     instruction_index += 1;
                 },
-                Instruction::Inc(variable) => { // should always have a prior mov
-                    self.registry.entry(variable.clone()).and_modify(|x| { *x +=1; });
+                Instruction::Inc(variable) => {
+                    let x = Self::register(&mut self.registry, variable, line)?;
+                    *x = x.wrapping_add(1);
                     instruction_index += 1;
                 },
-                Instruction::Dec(variable)  => { // should always have a prior mov
-                    self.registry.entry(variable.clone()).and_modify(|x| { *x -=1; });
+                Instruction::Dec(variable)  => {
+                    let x = Self::register(&mut self.registry, variable, line)?;
+                    *x = x.wrapping_sub(1);
+                    instruction_index += 1;
+                },
+                Instruction::Arith(apply, variable, value) => {
+                    let val = self.resolve_value(value, line)?;
+                    let x = Self::register(&mut self.registry, variable, line)?;
+                    *x = apply(*x, val);
+                    instruction_index += 1;
+                },
+                Instruction::Div(variable, value) => {
+                    let val = self.resolve_value(value, line)?;
+                    let x = Self::register(&mut self.registry, variable, line)?;
+                    if val == 0 {
+                        return Err(Outcome::DivisionByZero { line });
+                    }
+                    *x = x.wrapping_div(val);
                     instruction_index += 1;
                 },
                 Instruction::Jnz(variable, jump)  => {
-                    let val = self.resolve_value(variable);
+                    let val = self.resolve_value(variable, line)?;
                     // (ignore jump here to move to next instruction)
                     if val == 0 {
                         instruction_index += 1;
                     }
                     else {
-                        let val = self.resolve_value(jump);
-                        if val < 0 {
-                            instruction_index -= (val * -1) as usize;
+                        let val = self.resolve_value(jump, line)?;
+                        match isize::try_from(val).ok().and_then(|val| instruction_index.checked_add_signed(val)) {
+                            Some(target) => instruction_index = target,
+                            None if val < 0 => return Err(Outcome::JumpBeforeStart { line }),
+                            // past the end
+                            None => break,
                         }
-                        else {
-                            instruction_index += val as usize;
+                    }
+                },
+                Instruction::Cmp(x, y) => {
+                    comparison = Some(self.resolve_value(x, line)?.cmp(&self.resolve_value(y, line)?));
+                    instruction_index += 1;
+                },
+                Instruction::Jump(condition, target) => {
+                    // a conditional jump before any `cmp` is not taken
+                    let taken = match condition {
+                        None => true,
+                        Some(holds) => comparison.is_some_and(holds),
+                    };
+                    instruction_index = if taken { *target } else { instruction_index + 1 };
+                },
+                Instruction::Call(target) => {
+                    returns.push(instruction_index + 1);
+                    instruction_index = *target;
+                },
+                Instruction::Ret => {
+                    instruction_index = returns.pop().ok_or(Outcome::ReturnWithoutCall { line })?;
+                },
+                Instruction::Msg(parts) => {
+                    for part in parts {
+                        match part {
+                            Part::Text(text) => output.push_str(text),
+                            Part::Value(value) => output.push_str(&self.resolve_value(value, line)?.to_string()),
                         }
                     }
+                    instruction_index += 1;
                 },
+                Instruction::End => return Ok(output),
             }
         }
+        Err(Outcome::NoEnd)
+    }
+}
+
+/// The part II kata entry point: the output of `input`, `None` when it has
+/// no `end`. Panics like [`Program::new`] on a malformed program, and with
+/// the fault on one that fails at run time.
+pub fn assembler_interpreter(input: &str) -> Option<String> {
+    match Program::new(input.lines().collect()).run() {
+        Outcome::Ended(output) => Some(output),
+        Outcome::NoEnd => None,
+        fault => panic!("{}", fault),
     }
 }
 
@@ -151,6 +526,263 @@ mod tests {
         compare_registers(expected, program.registry);
     }
 
+    fn diagnostics(lines: &[&str]) -> Vec<(usize, Range<usize>, String)> {
+        match Program::parse(lines) {
+            Ok(_) => panic!("{:?} parsed", lines),
+            Err(found) => found.into_iter().map(|d| (d.line, d.span, d.message)).collect(),
+        }
+    }
+
+    #[test]
+    fn reports_every_malformed_line() {
+        assert_eq!(diagnostics(&["mov a"]), [(1, 5..5, "`mov` takes 2 operand(s), found 1".to_string())]);
+        assert_eq!(
+            diagnostics(&["mov a 1", "mov 5 a", "inc a b c", "", "jump 2", "jnz 1x 99999999999999999999"]),
+            [
+                (2, 4..5, "expected a register, found `5`".to_string()),
+                (3, 6..9, "unexpected `b c` after the operands of `inc`".to_string()),
+                (5, 0..4, "unknown instruction `jump`".to_string()),
+                (6, 4..6, "expected a register or an integer, found `1x`".to_string()),
+                (6, 7..27, "`99999999999999999999` does not fit in 64 bits".to_string()),
+            ]
+        );
+        assert!(Program::parse(&["mov a -5", "jnz a b"]).is_ok());
+    }
+
+    #[test]
+    fn reports_malformed_labels_and_messages() {
+        assert_eq!(
+            diagnostics(&[
+                "start:", "jmp finish", "jne 2", "call start, 1", "start: ; again", "1st: msg", "msg 'a', b c, 7x", "msg 'unterminated ; not a comment",
+            ]),
+            [
+                (2, 4..10, "unknown label `finish`".to_string()),
+                (3, 4..5, "expected a label, found `2`".to_string()),
+                (4, 12..13, "unexpected `1` after the operands of `call`".to_string()),
+                (5, 0..6, "label `start` is defined twice".to_string()),
+                (6, 0..4, "expected a label, found `1st`".to_string()),
+                (6, 8..8, "`msg` takes 1 or more operand(s), found 0".to_string()),
+                (7, 14..16, "expected a register or an integer, found `7x`".to_string()),
+                (8, 4..33, "unterminated string".to_string()),
+            ]
+        );
+        assert!(Program::from_source("  ; nothing but a comment\n\nloop: jmp loop").is_ok());
+    }
+
+    #[test]
+    fn runs_the_part_ii_samples() {
+        let samples = [
+            ("\
+; My first program
+mov  a, 5
+inc  a
+call function
+msg  '(5+1)/2 = ', a    ; output message
+end
+
+function:
+    div  a, 2
+    ret", Some("(5+1)/2 = 3")),
+            ("\
+mov   a, 5
+mov   b, a
+mov   c, a
+call  proc_fact
+call  print
+end
+
+proc_fact:
+    dec   b
+    mul   c, b
+    cmp   b, 1
+    jne   proc_fact
+    ret
+
+print:
+    msg   a, '! = ', c ; output text
+    ret", Some("5! = 120")),
+            ("\
+mov   a, 8            ; value
+mov   b, 0            ; next
+mov   c, 0            ; counter
+mov   d, 0            ; first
+mov   e, 1            ; second
+call  proc_fib
+call  print
+end
+
+proc_fib:
+    cmp   c, 2
+    jl    func_0
+    mov   b, d
+    add   b, e
+    mov   d, e
+    mov   e, b
+    inc   c
+    cmp   c, a
+    jle   proc_fib
+    ret
+
+func_0:
+    mov   b, c
+    inc   c
+    jmp   proc_fib
+
+print:
+    msg   'Term ', a, ' of Fibonacci series is: ', b        ; output text
+    ret", Some("Term 8 of Fibonacci series is: 21")),
+            ("\
+mov   a, 11           ; value1
+mov   b, 3            ; value2
+call  mod_func
+msg   'mod(', a, ', ', b, ') = ', d        ; output
+end
+
+; Mod function
+mod_func:
+    mov   c, a        ; temp1
+    div   c, b
+    mul   c, b
+    mov   d, a        ; temp2
+    sub   d, c
+    ret", Some("mod(11, 3) = 2")),
+            ("\
+mov   a, 81         ; value1
+mov   b, 153        ; value2
+call  init
+call  proc_gcd
+call  print
+end
+
+proc_gcd:
+    cmp   c, d
+    jne   loop
+    ret
+
+loop:
+    cmp   c, d
+    jg    a_bigger
+    jmp   b_bigger
+
+a_bigger:
+    sub   c, d
+    jmp   proc_gcd
+
+b_bigger:
+    sub   d, c
+    jmp   proc_gcd
+
+init:
+    cmp   a, 0
+    jne   a_positive
+    cmp   b, 0
+    jne   b_positive
+
+a_positive:
+    mov   c, a
+    mov   d, b
+    ret
+
+b_positive:
+    mov   c, b
+    mov   d, a
+    ret
+
+print:
+    msg   'gcd(', a, ', ', b, ') = ', c
+    ret", Some("gcd(81, 153) = 9")),
+            ("\
+call  func1
+call  print
+end
+
+func1:
+    call  func2
+    ret
+
+func2:
+    ret
+
+print:
+    msg 'This program should return -1'", None),
+            ("\
+mov   a, 2            ; value1
+mov   b, 10           ; value2
+mov   c, a            ; temp1
+mov   d, b            ; temp2
+call  proc_func
+call  print
+end
+
+proc_func:
+    cmp   d, 1
+    je    continue
+    mul   c, a
+    dec   d
+    call  proc_func
+
+continue:
+    ret
+
+print:
+    msg a, '^', b, ' = ', c
+    ret", Some("2^10 = 1024")),
+        ];
+        for (source, expected) in samples {
+            assert_eq!(assembler_interpreter(source).as_deref(), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn part_i_programs_keep_their_registers() {
+        let mut program = Program::from_source("mov a, -7\nmov b 2\ndiv a, b\nmsg a, ';', b\njnz b 2\nend\njg nowhere\nnowhere:").unwrap();
+        assert_eq!(program.run(), Outcome::NoEnd);
+        compare_registers(map! { "a" => -3, "b" => 2 }, program.registry);
+    }
+
+    #[test]
+    fn reports_runtime_faults_with_their_line() {
+        let faults = [
+            ("mov a 1\nmov a b\nend", Outcome::UnsetRegister { line: 2, register: "b".to_string() }),
+            ("inc a\nend", Outcome::UnsetRegister { line: 1, register: "a".to_string() }),
+            ("mov a 5\n; nothing\ndiv a 0\nend", Outcome::DivisionByZero { line: 3 }),
+            ("mov a 1\nret\nend", Outcome::ReturnWithoutCall { line: 2 }),
+            ("mov a 1\njnz a -2\nend", Outcome::JumpBeforeStart { line: 2 }),
+        ];
+        for (source, expected) in faults {
+            assert_eq!(Program::from_source(source).unwrap().run(), expected, "{}", source);
+        }
+        assert_eq!(Outcome::DivisionByZero { line: 3 }.to_string(), "line 3: division by zero");
+        let mut program = Program::from_source("mov a -9223372036854775808\ndiv a -1\nend").unwrap();
+        assert_eq!(program.run(), Outcome::Ended(String::new()));
+        assert_eq!(program.registry["a"], i64::MIN);
+    }
+
+    #[test]
+    #[should_panic(expected = "line 1: `ret` outside of a call")]
+    fn the_kata_entry_point_panics_with_the_fault() {
+        assembler_interpreter("ret\nend");
+    }
+
+    #[test]
+    fn renders_with_a_caret() {
+        let lines = ["mov a 1", "dec  7"];
+        let rendered: Vec<String> = Program::parse(&lines).err().unwrap().iter().map(|d| d.render(lines[d.line - 1])).collect();
+        assert_eq!(rendered, ["error: expected a register, found `7`\n  |\n2 | dec  7\n  |      ^"]);
+        let missing = &Program::parse(&["jnz a"]).err().unwrap()[0];
+        assert_eq!(missing.render("jnz a"), "error: `jnz` takes 2 operand(s), found 1\n  |\n1 | jnz a\n  |      ^");
+        assert_eq!(missing.to_string(), "line 1, column 6: `jnz` takes 2 operand(s), found 1");
+        let lines = ["loop:", "\tdec\t7"];
+        let tabbed = &Program::parse(&lines).err().unwrap()[0];
+        assert_eq!(tabbed.render(lines[1]), "error: expected a register, found `7`\n  |\n2 | \tdec\t7\n  | \t   \t^");
+    }
+
+    #[test]
+    #[should_panic(expected = "takes 2 operand(s)")]
+    fn the_kata_entry_point_panics_with_the_diagnostics() {
+        Program::new(vec!["mov a"]);
+    }
+
     fn compare_registers(expected: HashMap<String, i64>, actual: HashMap<String, i64>) {
         let result = expected
             .iter()
@@ -0,0 +1,288 @@
+//! Expression trees built from postfix input, identifiers included.
+//!
+//! `Display` prints infix with only the parentheses that precedence and
+//! left associativity require, so printing and parsing again gives back the
+//! same tree. [`Expr::to_postfix`] and [`Expr::to_prefix`] write the other
+//! two notations, tokens separated by single spaces.
+//!
+//! Words of the [`Registry`] that are not operators become calls, printed as
+//! `gcd(a, b)`. Stack words have no tree form and are rejected.
+
+use crate::formula::is_identifier;
+use crate::postfix::{self, tokens, ErrorKind, EvalError, ExprNode, Op, UnaryOp};
+use crate::registry::Registry;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Value(i64),
+    Variable(String),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+    /// Any other function word, operands deepest first.
+    Call(String, Vec<Expr>),
+}
+
+impl Expr {
+    /// Builds the tree of a postfix expression, failing like the evaluator
+    /// on malformed input.
+    pub fn from_postfix(expr: &str) -> Result<Expr, EvalError> {
+        postfix::with_standard(|registry| Expr::from_postfix_with(expr, registry))
+    }
+
+    /// [`Expr::from_postfix`] with the words of `registry`.
+    pub fn from_postfix_with(expr: &str, registry: &Registry<i64>) -> Result<Expr, EvalError> {
+        // subtrees with the position of their last token
+        let mut stack: Vec<(Expr, usize, usize)> = Vec::new();
+        let mut count = 0;
+        for (token, (offset, s)) in tokens(expr).enumerate() {
+            count += 1;
+            let error = |kind| EvalError {
+                kind,
+                token,
+                offset,
+            };
+            let node = match ExprNode::from_str(s, registry) {
+                Err(_) if is_identifier(s) => Expr::Variable(s.to_string()),
+                node => match node.map_err(error)? {
+                    ExprNode::Value(v) => Expr::Value(v),
+                    ExprNode::Word(word) => {
+                        ExprNode::function(word, s).map_err(error)?;
+                        let Some(base) = stack.len().checked_sub(word.arity) else {
+                            return Err(error(ErrorKind::StackUnderflow));
+                        };
+                        let mut operands = stack.drain(base..).map(|(operand, _, _)| operand);
+                        match (Op::from_symbol(s), UnaryOp::from_symbol(s), word.arity) {
+                            (Some(op), _, 2) => {
+                                let lhs = operands.next().unwrap();
+                                let rhs = operands.next().unwrap();
+                                Expr::Binary(op, Box::new(lhs), Box::new(rhs))
+                            }
+                            (_, Some(op), 1) => Expr::Unary(op, Box::new(operands.next().unwrap())),
+                            _ => Expr::Call(s.to_string(), operands.collect()),
+                        }
+                    }
+                },
+            };
+            stack.push((node, token, offset));
+        }
+        match stack.len() {
+            0 => Err(EvalError {
+                kind: ErrorKind::StackUnderflow,
+                token: count,
+                offset: expr.len(),
+            }),
+            1 => Ok(stack.pop().unwrap().0),
+            n => {
+                let (_, token, offset) = stack[0];
+                Err(EvalError {
+                    kind: ErrorKind::LeftoverOperands(n - 1),
+                    token,
+                    offset,
+                })
+            }
+        }
+    }
+
+    pub fn to_postfix(&self) -> String {
+        match self {
+            Expr::Value(v) => v.to_string(),
+            Expr::Variable(name) => name.clone(),
+            Expr::Unary(op, operand) => format!("{} {}", operand.to_postfix(), op.symbol()),
+            Expr::Binary(op, lhs, rhs) => {
+                format!("{} {} {}", lhs.to_postfix(), rhs.to_postfix(), op.symbol())
+            }
+            Expr::Call(name, operands) => {
+                let mut tokens: Vec<String> = operands.iter().map(Expr::to_postfix).collect();
+                tokens.push(name.clone());
+                tokens.join(" ")
+            }
+        }
+    }
+
+    pub fn to_prefix(&self) -> String {
+        match self {
+            Expr::Value(v) => v.to_string(),
+            Expr::Variable(name) => name.clone(),
+            Expr::Unary(op, operand) => format!("{} {}", op.symbol(), operand.to_prefix()),
+            Expr::Binary(op, lhs, rhs) => {
+                format!("{} {} {}", op.symbol(), lhs.to_prefix(), rhs.to_prefix())
+            }
+            Expr::Call(name, operands) => {
+                let mut tokens = vec![name.clone()];
+                tokens.extend(operands.iter().map(Expr::to_prefix));
+                tokens.join(" ")
+            }
+        }
+    }
+
+    /// How tightly the top of the tree binds when printed as infix. Negative
+    /// literals count as negations, so that `-(-1)` keeps its parentheses.
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Value(v) if *v < 0 => 3,
+            Expr::Value(_) | Expr::Variable(_) | Expr::Call(..) => 5,
+            Expr::Binary(Op::Power, ..) => 4,
+            Expr::Unary(..) => 3,
+            Expr::Binary(Op::Add | Op::Subtract, ..) => 1,
+            Expr::Binary(Op::Multiply | Op::Divide | Op::Remainder, ..) => 2,
+        }
+    }
+
+    fn fmt_operand(&self, f: &mut fmt::Formatter<'_>, parenthesize: bool) -> fmt::Result {
+        if parenthesize {
+            write!(f, "({})", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Value(v) => write!(f, "{}", v),
+            Expr::Variable(name) => f.write_str(name),
+            Expr::Unary(UnaryOp::Negate, operand) => {
+                f.write_str("-")?;
+                operand.fmt_operand(f, operand.precedence() <= self.precedence())
+            }
+            Expr::Binary(Op::Power, lhs, rhs) => {
+                // right associative, the mirror image of the others
+                lhs.fmt_operand(f, lhs.precedence() <= self.precedence())?;
+                f.write_str(" ^ ")?;
+                rhs.fmt_operand(f, rhs.precedence() < self.precedence())
+            }
+            Expr::Binary(op, lhs, rhs) => {
+                // left associative, so an operand of the same precedence only
+                // needs parentheses on the right
+                lhs.fmt_operand(f, lhs.precedence() < self.precedence())?;
+                write!(f, " {} ", op.symbol())?;
+                rhs.fmt_operand(f, rhs.precedence() <= self.precedence())
+            }
+            Expr::Call(name, operands) => {
+                write!(f, "{}(", name)?;
+                for (i, operand) in operands.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", operand)?;
+                }
+                f.write_str(")")
+            }
+        }
+    }
+}
+
+/// A random tree at most `depth` deep, of negations and the binary `ops`
+/// over `leaves`, for the property tests.
+#[cfg(test)]
+pub(crate) fn random_tree(
+    rng: &mut tools::stress::Rng,
+    depth: u32,
+    leaves: &[Expr],
+    ops: &[Op],
+) -> Expr {
+    match rng.range(0, 5) {
+        _ if depth == 0 => rng.pick(leaves).clone(),
+        0 => rng.pick(leaves).clone(),
+        1 => Expr::Unary(
+            UnaryOp::Negate,
+            Box::new(random_tree(rng, depth - 1, leaves, ops)),
+        ),
+        _ => Expr::Binary(
+            *rng.pick(ops),
+            Box::new(random_tree(rng, depth - 1, leaves, ops)),
+            Box::new(random_tree(rng, depth - 1, leaves, ops)),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infix;
+    use tools::stress::Rng;
+
+    fn printed(postfix: &str) -> String {
+        Expr::from_postfix(postfix).unwrap().to_string()
+    }
+
+    #[test]
+    fn prints_minimal_parentheses() {
+        assert_eq!(
+            printed("4 8 + 6 5 - * 3 2 - 2 2 + * /"),
+            "(4 + 8) * (6 - 5) / ((3 - 2) * (2 + 2))"
+        );
+        assert_eq!(printed("1 2 3 * +"), "1 + 2 * 3");
+        assert_eq!(printed("1 2 - 3 -"), "1 - 2 - 3");
+        assert_eq!(printed("1 2 3 - -"), "1 - (2 - 3)");
+        assert_eq!(printed("1 2 3 + +"), "1 + (2 + 3)");
+        assert_eq!(printed("1 2 / 3 *"), "1 / 2 * 3");
+        assert_eq!(printed("1 2 + neg 3 *"), "-(1 + 2) * 3");
+        assert_eq!(printed("-1 neg neg"), "-(-(-1))");
+        assert_eq!(printed("2 -3 -"), "2 - -3");
+        assert_eq!(printed("2 3 ^ 2 ^"), "(2 ^ 3) ^ 2");
+        assert_eq!(printed("2 3 2 ^ ^"), "2 ^ 3 ^ 2");
+        assert_eq!(printed("x 2 ^ neg -2 x neg ^ *"), "-x ^ 2 * (-2) ^ (-x)");
+    }
+
+    #[test]
+    fn writes_prefix_and_postfix() {
+        let expr = Expr::from_postfix("3 4 +  2 neg *").unwrap();
+        assert_eq!(expr.to_postfix(), "3 4 + 2 neg *");
+        assert_eq!(expr.to_prefix(), "* + 3 4 neg 2");
+        let expr = Expr::from_postfix("rate 1 + years * neg").unwrap();
+        assert_eq!(expr.to_string(), "-((rate + 1) * years)");
+        assert_eq!(expr.to_prefix(), "neg * + rate 1 years");
+    }
+
+    #[test]
+    fn reads_every_function_word() {
+        let expr = Expr::from_postfix("a b gcd 7 % c abs 2 max -").unwrap();
+        assert_eq!(expr.to_string(), "gcd(a, b) % 7 - max(abs(c), 2)");
+        assert_eq!(expr.to_postfix(), "a b gcd 7 % c abs 2 max -");
+        assert_eq!(expr.to_prefix(), "- % gcd a b 7 max abs c 2");
+        assert_eq!(printed("1 2 3 + %"), "1 % (2 + 3)");
+
+        let mut registry = Registry::<i64>::standard();
+        registry.define("clamp", 3, |a| Ok(a[0].clamp(a[1], a[2])));
+        let expr = Expr::from_postfix_with("x 0 10 clamp 1 +", &registry).unwrap();
+        assert_eq!(expr.to_string(), "clamp(x, 0, 10) + 1");
+    }
+
+    #[test]
+    fn stack_words_have_no_tree() {
+        let e = Expr::from_postfix("x dup *").unwrap_err();
+        assert_eq!(
+            (e.kind, e.token, e.offset),
+            (ErrorKind::StackWord("dup".to_string()), 1, 2)
+        );
+    }
+
+    #[test]
+    fn reports_malformed_postfix() {
+        let e = Expr::from_postfix("1 +").unwrap_err();
+        assert_eq!(
+            (e.kind, e.token, e.offset),
+            (ErrorKind::StackUnderflow, 1, 2)
+        );
+        let e = Expr::from_postfix("1 2").unwrap_err();
+        assert_eq!(e.kind, ErrorKind::LeftoverOperands(1));
+    }
+
+    #[test]
+    fn round_trips() {
+        let leaves: Vec<Expr> = (-9..=9).map(Expr::Value).collect();
+        let mut rng = Rng::new(14);
+        for _ in 0..2000 {
+            let tree = random_tree(&mut rng, 5, &leaves, &Op::ALL);
+            assert_eq!(Expr::from_postfix(&tree.to_postfix()).as_ref(), Ok(&tree));
+            let printed = tree.to_string();
+            let reparsed = infix::infix_to_postfix(&printed).unwrap();
+            // a negative literal reads back as a negated one
+            let normalized = Expr::from_postfix(&reparsed).unwrap();
+            assert_eq!(normalized.to_string(), printed, "{:?}", tree);
+        }
+    }
+}
@@ -0,0 +1,76 @@
+//! The same formula over many rows: parsed from a string per row, as
+//! `postfix_evaluator` does, against compiled once.
+//!
+//! `cargo bench -p codewars`
+
+use codewars::compiled::CompiledExpr;
+use codewars::formula::Formula;
+use codewars::postfix::try_postfix_evaluator;
+use codewars::registry::Registry;
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::time::Instant;
+
+const FORMULA: &str = "price qty * 100 discount - * 100 / tax + dup 0 max swap drop";
+const ROWS: i64 = 1_000_000;
+
+fn row(i: i64) -> [i64; 4] {
+    [i % 997, i % 13 + 1, i % 50, i % 7]
+}
+
+fn time(name: &str, mut f: impl FnMut() -> i64) {
+    let start = Instant::now();
+    let checksum = black_box(f());
+    let elapsed = start.elapsed();
+    println!(
+        "{:<12} {:>8.1} ns/row  (checksum {})",
+        name,
+        elapsed.as_nanos() as f64 / ROWS as f64,
+        checksum
+    );
+}
+
+fn main() {
+    let registry = Registry::standard();
+    let names = ["price", "qty", "discount", "tax"];
+
+    // numbers substituted into the text beforehand, so only evaluation counts
+    let texts: Vec<String> = (0..ROWS)
+        .map(|i| {
+            let values = row(i);
+            names
+                .iter()
+                .zip(values)
+                .fold(FORMULA.to_string(), |text, (name, value)| {
+                    text.replace(name, &value.to_string())
+                })
+        })
+        .collect();
+    time("string", || {
+        texts
+            .iter()
+            .map(|text| try_postfix_evaluator(black_box(text)).unwrap())
+            .sum()
+    });
+
+    time("formula", || {
+        let formula = Formula::parse(FORMULA, &registry).unwrap();
+        let mut env = HashMap::new();
+        (0..ROWS)
+            .map(|i| {
+                for (name, value) in names.iter().zip(row(i)) {
+                    env.insert(name.to_string(), value);
+                }
+                formula.eval(&mut env).unwrap()
+            })
+            .sum()
+    });
+
+    time("compiled", || {
+        let mut compiled = CompiledExpr::compile(FORMULA, &registry).unwrap();
+        assert_eq!(compiled.inputs(), names);
+        (0..ROWS)
+            .map(|i| compiled.eval(black_box(&row(i))).unwrap())
+            .sum()
+    });
+}
@@ -0,0 +1,335 @@
+//! Arbitrary-precision integers for the evaluators, so that no intermediate
+//! result overflows. Division truncates toward zero, as `i64` does.
+
+use crate::numeric::{is_integer_literal, power, Number};
+use crate::postfix::ErrorKind;
+use std::cmp::Ordering;
+use std::fmt;
+
+const BASE: u64 = 1_000_000_000;
+
+/// Sign and magnitude, the magnitude in base 10^9 limbs, least significant
+/// first, without trailing zero limbs. Zero has no limbs and is not negative.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BigInt {
+    negative: bool,
+    limbs: Vec<u32>,
+}
+
+impl BigInt {
+    fn new(negative: bool, mut limbs: Vec<u32>) -> Self {
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+        BigInt {
+            negative: negative && !limbs.is_empty(),
+            limbs,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(n: i64) -> Self {
+        let mut magnitude = n.unsigned_abs();
+        let mut limbs = Vec::new();
+        while magnitude > 0 {
+            limbs.push((magnitude % BASE) as u32);
+            magnitude /= BASE;
+        }
+        BigInt::new(n < 0, limbs)
+    }
+}
+
+fn compare(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut sum = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0;
+    for i in 0..a.len().max(b.len()) {
+        let limb = carry + *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64;
+        sum.push((limb % BASE) as u32);
+        carry = limb / BASE;
+    }
+    sum.push(carry as u32);
+    sum
+}
+
+/// `a - b` for `a >= b`.
+fn sub(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut difference = Vec::with_capacity(a.len());
+    let mut borrow = 0;
+    for (i, &limb) in a.iter().enumerate() {
+        let subtrahend = borrow + *b.get(i).unwrap_or(&0) as i64;
+        let mut limb = limb as i64 - subtrahend;
+        borrow = 0;
+        if limb < 0 {
+            limb += BASE as i64;
+            borrow = 1;
+        }
+        difference.push(limb as u32);
+    }
+    difference
+}
+
+fn mul(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut product = vec![0u64; a.len() + b.len() + 1];
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0;
+        for (j, &y) in b.iter().enumerate() {
+            let limb = product[i + j] + x as u64 * y as u64 + carry;
+            product[i + j] = limb % BASE;
+            carry = limb / BASE;
+        }
+        product[i + b.len()] += carry;
+    }
+    product.into_iter().map(|limb| limb as u32).collect()
+}
+
+fn trim(mut limbs: Vec<u32>) -> Vec<u32> {
+    while limbs.last() == Some(&0) {
+        limbs.pop();
+    }
+    limbs
+}
+
+/// Truncated quotient of two magnitudes, `b` non-zero. Schoolbook long
+/// division, each quotient limb found by binary search.
+fn div(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut quotient = vec![0; a.len()];
+    let mut remainder: Vec<u32> = Vec::new();
+    for i in (0..a.len()).rev() {
+        remainder.insert(0, a[i]);
+        remainder = trim(remainder);
+        let (mut lo, mut hi) = (0, BASE as u32 - 1);
+        while lo < hi {
+            let mid = lo + (hi - lo).div_ceil(2);
+            if compare(&trim(mul(b, &[mid])), &remainder) == Ordering::Greater {
+                hi = mid - 1;
+            } else {
+                lo = mid;
+            }
+        }
+        quotient[i] = lo;
+        remainder = trim(sub(&remainder, &trim(mul(b, &[lo]))));
+    }
+    quotient
+}
+
+impl Number for BigInt {
+    fn parse(token: &str) -> Result<Self, ErrorKind> {
+        if !is_integer_literal(token) {
+            return Err(ErrorKind::UnknownToken(token.to_string()));
+        }
+        let digits = token.trim_start_matches(['-', '+']).as_bytes();
+        let limbs = digits
+            .rchunks(9)
+            .map(|chunk| chunk.iter().fold(0, |n, d| n * 10 + (d - b'0') as u32))
+            .collect();
+        Ok(BigInt::new(token.starts_with('-'), limbs))
+    }
+
+    fn from_i64(n: i64) -> Self {
+        BigInt::from(n)
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        if self.limbs.len() > 3 {
+            return None;
+        }
+        let magnitude = self
+            .limbs
+            .iter()
+            .rev()
+            .fold(0i128, |n, &limb| n * BASE as i128 + limb as i128);
+        let n = if self.negative { -magnitude } else { magnitude };
+        n.try_into().ok()
+    }
+
+    fn add(&self, rhs: &Self) -> Result<Self, ErrorKind> {
+        if self.negative == rhs.negative {
+            return Ok(BigInt::new(self.negative, add(&self.limbs, &rhs.limbs)));
+        }
+        // opposite signs, the larger magnitude keeps its sign
+        Ok(match compare(&self.limbs, &rhs.limbs) {
+            Ordering::Less => BigInt::new(rhs.negative, sub(&rhs.limbs, &self.limbs)),
+            _ => BigInt::new(self.negative, sub(&self.limbs, &rhs.limbs)),
+        })
+    }
+
+    fn sub(&self, rhs: &Self) -> Result<Self, ErrorKind> {
+        self.add(&rhs.neg()?)
+    }
+
+    fn mul(&self, rhs: &Self) -> Result<Self, ErrorKind> {
+        Ok(BigInt::new(
+            self.negative != rhs.negative,
+            mul(&self.limbs, &rhs.limbs),
+        ))
+    }
+
+    fn div(&self, rhs: &Self) -> Result<Self, ErrorKind> {
+        if rhs.is_zero() {
+            return Err(ErrorKind::DivisionByZero);
+        }
+        Ok(BigInt::new(
+            self.negative != rhs.negative,
+            div(&self.limbs, &rhs.limbs),
+        ))
+    }
+
+    fn rem(&self, rhs: &Self) -> Result<Self, ErrorKind> {
+        self.sub(&self.div(rhs)?.mul(rhs)?)
+    }
+
+    fn neg(&self) -> Result<Self, ErrorKind> {
+        Ok(BigInt::new(!self.negative, self.limbs.clone()))
+    }
+
+    /// Exponents times base digits beyond a million count as overflow,
+    /// rather than exhausting memory; 0, 1 and -1 take any exponent.
+    fn pow(&self, exponent: &Self) -> Result<Self, ErrorKind> {
+        let trivial = self.limbs.len() <= 1 && self.limbs.first().is_none_or(|&limb| limb == 1);
+        let digits = self.to_string().trim_start_matches('-').len() as i64;
+        match exponent.to_i64() {
+            Some(e) if !trivial && e.saturating_mul(digits) > 1_000_000 => Err(ErrorKind::Overflow),
+            None if trivial && !exponent.negative => {
+                // only the parity of a huge exponent matters
+                if self.is_zero() || !exponent.rem(&BigInt::from(2))?.is_zero() {
+                    Ok(self.clone())
+                } else {
+                    Ok(BigInt::from(1))
+                }
+            }
+            _ => power(self, exponent),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compare(&self.limbs, &other.limbs),
+            (true, true) => compare(&other.limbs, &self.limbs),
+        }
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some((most, rest)) = self.limbs.split_last() else {
+            return f.write_str("0");
+        };
+        if self.negative {
+            f.write_str("-")?;
+        }
+        write!(f, "{}", most)?;
+        for limb in rest.iter().rev() {
+            write!(f, "{:09}", limb)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tools::stress::Rng;
+
+    fn big(s: &str) -> BigInt {
+        BigInt::parse(s).unwrap()
+    }
+
+    #[test]
+    fn parses_and_prints() {
+        for s in ["0", "7", "-1000000000", "123456789012345678901234567890"] {
+            assert_eq!(big(s).to_string(), s);
+        }
+        assert_eq!(big("-0"), BigInt::default());
+        assert_eq!(big("+0007").to_string(), "7");
+        assert!(BigInt::parse("1.5").is_err());
+    }
+
+    #[test]
+    fn exceeds_i64() {
+        let max = BigInt::from(i64::MAX);
+        assert_eq!(
+            max.mul(&max).unwrap().to_string(),
+            "85070591730234615847396907784232501249"
+        );
+        assert_eq!(
+            big("85070591730234615847396907784232501249")
+                .div(&max)
+                .unwrap(),
+            max
+        );
+        assert_eq!(
+            BigInt::from(i64::MIN).neg().unwrap().to_string(),
+            "9223372036854775808"
+        );
+    }
+
+    #[test]
+    fn remainders_powers_and_gcd() {
+        assert_eq!(big("-7").rem(&big("2")), Ok(big("-1")));
+        assert_eq!(
+            big("2").pow(&big("100")).unwrap().to_string(),
+            "1267650600228229401496703205376"
+        );
+        assert_eq!(big("10").pow(&big("2000000")), Err(ErrorKind::Overflow));
+        assert_eq!(big("-1").pow(&big("99999999999999999999")), Ok(big("-1")));
+        assert_eq!(big("-1").pow(&big("99999999999999999998")), Ok(big("1")));
+        assert_eq!(big("0").pow(&big("99999999999999999998")), Ok(big("0")));
+        assert_eq!(
+            big("1267650600228229401496703205376").gcd(&big("-6")),
+            Ok(big("2"))
+        );
+        assert!(big("-5") < big("3") && big("-5") < big("-4") && big("10") > big("9"));
+    }
+
+    #[test]
+    fn agrees_with_i128() {
+        let mut rng = Rng::new(16);
+        for _ in 0..5000 {
+            let a = rng.range(i64::MIN, i64::MAX) >> rng.range(0, 62);
+            let b = rng.range(i64::MIN, i64::MAX) >> rng.range(0, 62);
+            if b == 0 {
+                continue;
+            }
+            let (x, y) = (BigInt::from(a), BigInt::from(b));
+            let (a, b) = (a as i128, b as i128);
+            let (found, expected) = match rng.range(0, 3) {
+                0 => (x.add(&y), a + b),
+                1 => (x.sub(&y), a - b),
+                2 => (x.mul(&y), a * b),
+                _ => (x.div(&y), a / b),
+            };
+            assert_eq!(
+                found.unwrap().to_string(),
+                expected.to_string(),
+                "{} {}",
+                a,
+                b
+            );
+        }
+        assert_eq!(
+            BigInt::from(5).div(&BigInt::default()),
+            Err(ErrorKind::DivisionByZero)
+        );
+    }
+}
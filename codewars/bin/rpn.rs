@@ -0,0 +1,82 @@
+//! A reverse Polish calculator with a stack that persists between lines.
+//!
+//! Usage: `rpn [--eval EXPR | SCRIPT]`
+//!
+//! Without arguments lines are read from stdin and the stack is printed after
+//! each one. `--eval` runs a single line, a script file runs line by line,
+//! printing the stack on `show`; both print the final stack and stop at the
+//! first error. See `codewars::calculator` for the language.
+
+use codewars::calculator::{Calculator, Reply};
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.as_slice() {
+        [] => interactive(),
+        [flag, expr] if flag == "--eval" => batch(None, expr),
+        [flag] if flag == "--eval" => usage("--eval needs an expression"),
+        [script] if !script.starts_with('-') => match fs::read_to_string(script) {
+            Ok(source) => batch(Some(script), &source),
+            Err(e) => usage(&format!("{}: {}", script, e)),
+        },
+        _ => usage("unexpected arguments"),
+    }
+}
+
+fn interactive() -> ExitCode {
+    let mut calculator = Calculator::new();
+    let stdin = io::stdin();
+    let prompt = stdin.is_terminal();
+    let mut line = String::new();
+    loop {
+        if prompt {
+            print!("> ");
+            let _ = io::stdout().flush();
+        }
+        line.clear();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) => return ExitCode::SUCCESS,
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("rpn: {}", e);
+                return ExitCode::FAILURE;
+            }
+        }
+        let line = line.trim_end_matches(['\n', '\r']);
+        match calculator.run_line(line) {
+            Ok(Reply::Nothing) => {}
+            Ok(_) => println!("{}", calculator.show()),
+            Err(e) => eprintln!("{}", e.render(line)),
+        }
+    }
+}
+
+/// Runs `source` line by line, the first error located in `script` if any.
+fn batch(script: Option<&str>, source: &str) -> ExitCode {
+    let mut calculator = Calculator::new();
+    for (number, line) in source.lines().enumerate() {
+        match calculator.run_line(line) {
+            Ok(Reply::Show) => println!("{}", calculator.show()),
+            Ok(_) => {}
+            Err(e) => {
+                if let Some(script) = script {
+                    eprintln!("{}:{}:", script, number + 1);
+                }
+                eprintln!("{}", e.render(line));
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    println!("{}", calculator.show());
+    ExitCode::SUCCESS
+}
+
+fn usage(message: &str) -> ExitCode {
+    eprintln!("rpn: {}", message);
+    eprintln!("usage: rpn [--eval EXPR | SCRIPT]");
+    ExitCode::from(2)
+}
@@ -0,0 +1,193 @@
+//! A reverse Polish calculator whose stack persists from one line to the
+//! next, driven by the `rpn` binary.
+//!
+//! A line is either one of the commands `show`, `clear` and `undo`, or
+//! numbers and words of the standard [`Registry`] applied to the stack in
+//! order. A line that fails leaves the stack as it was. `#` starts a comment.
+
+use crate::postfix::{tokens, underline, EvalError, ExprNode};
+use crate::registry::Registry;
+use std::fmt;
+use std::mem;
+
+/// What the caller should do after a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reply {
+    /// The line was blank or a comment.
+    Nothing,
+    /// The stack changed.
+    Updated,
+    /// `show` was asked for.
+    Show,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CalcError {
+    /// A token of the line failed, located in the line.
+    Eval(EvalError),
+    NothingToUndo,
+}
+
+impl fmt::Display for CalcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CalcError::Eval(e) => e.kind.fmt(f),
+            CalcError::NothingToUndo => f.write_str("nothing to undo"),
+        }
+    }
+}
+
+impl CalcError {
+    /// `line` with the failing token underlined and the message after the
+    /// carets, or just the message when no token is to blame.
+    pub fn render(&self, line: &str) -> String {
+        let CalcError::Eval(e) = self else {
+            return self.to_string();
+        };
+        let width = line[e.offset..]
+            .split_whitespace()
+            .next()
+            .map_or(1, |token| token.chars().count());
+        format!(
+            "{}\n{} {}",
+            line.trim_end(),
+            underline(&line[..e.offset], width),
+            self
+        )
+    }
+}
+
+pub struct Calculator {
+    stack: Vec<i64>,
+    /// The stack before each line that changed it, for `undo`.
+    history: Vec<Vec<i64>>,
+    registry: Registry<i64>,
+}
+
+impl Default for Calculator {
+    fn default() -> Self {
+        Calculator::new()
+    }
+}
+
+impl Calculator {
+    pub fn new() -> Self {
+        Calculator {
+            stack: Vec::new(),
+            history: Vec::new(),
+            registry: Registry::standard(),
+        }
+    }
+
+    /// Bottom first.
+    pub fn stack(&self) -> &[i64] {
+        &self.stack
+    }
+
+    pub fn run_line(&mut self, line: &str) -> Result<Reply, CalcError> {
+        let code = line.split('#').next().unwrap_or_default();
+        match code.trim() {
+            "" => return Ok(Reply::Nothing),
+            "show" => return Ok(Reply::Show),
+            "clear" => {
+                self.history.push(mem::take(&mut self.stack));
+                return Ok(Reply::Updated);
+            }
+            "undo" => {
+                self.stack = self.history.pop().ok_or(CalcError::NothingToUndo)?;
+                return Ok(Reply::Updated);
+            }
+            _ => {}
+        }
+        let mut stack = self.stack.clone();
+        for (token, (offset, s)) in tokens(code).enumerate() {
+            let error = |kind| {
+                CalcError::Eval(EvalError {
+                    kind,
+                    token,
+                    offset,
+                })
+            };
+            match ExprNode::from_str(s, &self.registry).map_err(error)? {
+                ExprNode::Word(word) => word.apply(&mut stack).map_err(error)?,
+                ExprNode::Value(value) => stack.push(value),
+            }
+        }
+        self.history.push(mem::replace(&mut self.stack, stack));
+        Ok(Reply::Updated)
+    }
+
+    /// The stack on one line, bottom first.
+    pub fn show(&self) -> String {
+        if self.stack.is_empty() {
+            return "(empty)".to_string();
+        }
+        let values: Vec<String> = self.stack.iter().map(|v| v.to_string()).collect();
+        values.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::postfix::ErrorKind;
+
+    fn run(calculator: &mut Calculator, lines: &[&str]) {
+        for line in lines {
+            calculator.run_line(line).unwrap();
+        }
+    }
+
+    #[test]
+    fn keeps_the_stack_between_lines() {
+        let mut calculator = Calculator::new();
+        run(
+            &mut calculator,
+            &["2 3", "+ 4", "# a comment", "* dup # squared next"],
+        );
+        assert_eq!(calculator.stack(), [20, 20]);
+        assert_eq!(calculator.run_line("*"), Ok(Reply::Updated));
+        assert_eq!(calculator.run_line("show"), Ok(Reply::Show));
+        assert_eq!(calculator.run_line("  "), Ok(Reply::Nothing));
+        assert_eq!(calculator.show(), "400");
+    }
+
+    #[test]
+    fn clears_and_undoes() {
+        let mut calculator = Calculator::new();
+        run(&mut calculator, &["1 2", "swap", "clear", "5"]);
+        assert_eq!(calculator.show(), "5");
+        run(&mut calculator, &["undo"]);
+        assert_eq!(calculator.show(), "(empty)");
+        run(&mut calculator, &["undo"]);
+        assert_eq!(calculator.show(), "2 1");
+        run(&mut calculator, &["undo", "undo"]);
+        assert_eq!(calculator.run_line("undo"), Err(CalcError::NothingToUndo));
+    }
+
+    #[test]
+    fn a_failing_line_changes_nothing() {
+        let mut calculator = Calculator::new();
+        run(&mut calculator, &["10 2"]);
+        let e = calculator.run_line("3 + 0 / 1").unwrap_err();
+        assert_eq!(
+            e,
+            CalcError::Eval(EvalError {
+                kind: ErrorKind::DivisionByZero,
+                token: 3,
+                offset: 6
+            })
+        );
+        assert_eq!(calculator.show(), "10 2");
+        assert_eq!(e.render("3 + 0 / 1"), "3 + 0 / 1\n      ^ division by zero");
+        let e = calculator.run_line("1 2 three +").unwrap_err();
+        assert_eq!(
+            e.render("1 2 three +"),
+            "1 2 three +\n    ^^^^^ unknown token `three`"
+        );
+        run(&mut calculator, &["undo"]);
+        assert_eq!(calculator.show(), "(empty)");
+        let e = calculator.run_line("\tdup  +").unwrap_err();
+        assert_eq!(e.render("\tdup  +"), "\tdup  +\n\t^^^ not enough operands");
+    }
+}
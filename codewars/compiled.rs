@@ -0,0 +1,267 @@
+//! Postfix formulas compiled once and evaluated many times, for the same
+//! formula over millions of rows.
+//!
+//! Compiling checks the stack depth at every instruction, so evaluation
+//! cannot underflow or leave operands behind, and turns every name into a
+//! slot: the formula's inputs first, in order of first use, then the names it
+//! only assigns. Evaluation reuses the stack and slots of the previous call.
+
+use crate::formula::{Formula, Term};
+use crate::numeric::Number;
+use crate::postfix::{ErrorKind, EvalError};
+use crate::registry::{Registry, Word};
+
+enum Instr<'r, N> {
+    Push(N),
+    Load(usize),
+    Store(usize),
+    Word(&'r Word<N>),
+}
+
+pub struct CompiledExpr<'r, N> {
+    code: Vec<Instr<'r, N>>,
+    /// The token index and byte offset of each instruction, for errors.
+    positions: Vec<(usize, usize)>,
+    inputs: Vec<String>,
+    slots: Vec<N>,
+    stack: Vec<N>,
+}
+
+impl<'r, N: Number> CompiledExpr<'r, N> {
+    /// Fails with the error evaluating `expr` would give whatever the
+    /// bindings, anything but a failing operation or an unbound name.
+    pub fn compile(expr: &str, registry: &'r Registry<N>) -> Result<Self, EvalError> {
+        let formula = Formula::parse(expr, registry)?;
+        let inputs: Vec<String> = formula.inputs().into_iter().map(String::from).collect();
+        let mut names = inputs.clone();
+        let mut slot = |name: String| match names.iter().position(|n| *n == name) {
+            Some(slot) => slot,
+            None => {
+                names.push(name);
+                names.len() - 1
+            }
+        };
+
+        let mut code = Vec::with_capacity(formula.terms.len());
+        let mut positions = Vec::with_capacity(formula.terms.len());
+        // the position of the token that produces each operand
+        let mut origins: Vec<(usize, usize)> = Vec::new();
+        let mut depth = 0;
+        for (term, token, offset, _) in formula.terms {
+            let underflow = EvalError {
+                kind: ErrorKind::StackUnderflow,
+                token,
+                offset,
+            };
+            let instr = match term {
+                Term::Value(value) => Instr::Push(value),
+                Term::Load(name) => Instr::Load(slot(name)),
+                Term::Store(name) => {
+                    origins.pop().ok_or(underflow)?;
+                    Instr::Store(slot(name))
+                }
+                Term::Word(word) => {
+                    let base = origins.len().checked_sub(word.arity).ok_or(underflow)?;
+                    // results go on top before the operands are removed
+                    depth = depth.max(origins.len() + word.results());
+                    origins.truncate(base);
+                    Instr::Word(word)
+                }
+            };
+            let results = match &instr {
+                Instr::Push(_) | Instr::Load(_) => 1,
+                Instr::Store(_) => 0,
+                Instr::Word(word) => word.results(),
+            };
+            origins.extend(std::iter::repeat_n((token, offset), results));
+            depth = depth.max(origins.len());
+            code.push(instr);
+            positions.push((token, offset));
+        }
+        match origins.len() {
+            0 => {
+                let (token, offset) = formula.end;
+                return Err(EvalError {
+                    kind: ErrorKind::StackUnderflow,
+                    token,
+                    offset,
+                });
+            }
+            1 => {}
+            n => {
+                let (token, offset) = origins[0];
+                return Err(EvalError {
+                    kind: ErrorKind::LeftoverOperands(n - 1),
+                    token,
+                    offset,
+                });
+            }
+        }
+
+        Ok(CompiledExpr {
+            code,
+            positions,
+            slots: vec![N::from_i64(0); names.len()],
+            inputs,
+            stack: Vec::with_capacity(depth),
+        })
+    }
+
+    /// The names [`CompiledExpr::eval`] takes values for, in that order.
+    pub fn inputs(&self) -> &[String] {
+        &self.inputs
+    }
+
+    /// Evaluates with `bindings[i]` as the value of `inputs()[i]`. Allocates
+    /// nothing unless cloning an `N` does.
+    ///
+    /// # Panics
+    ///
+    /// If `bindings` is not as long as [`CompiledExpr::inputs`].
+    pub fn eval(&mut self, bindings: &[N]) -> Result<N, EvalError> {
+        assert_eq!(
+            bindings.len(),
+            self.inputs.len(),
+            "expected a binding for each of {:?}",
+            self.inputs
+        );
+        self.slots[..bindings.len()].clone_from_slice(bindings);
+        self.stack.clear();
+        for (instr, &(token, offset)) in self.code.iter().zip(&self.positions) {
+            match instr {
+                Instr::Push(value) => self.stack.push(value.clone()),
+                Instr::Load(slot) => self.stack.push(self.slots[*slot].clone()),
+                Instr::Store(slot) => self.slots[*slot] = self.stack.pop().unwrap(),
+                Instr::Word(word) => word.apply(&mut self.stack).map_err(|kind| EvalError {
+                    kind,
+                    token,
+                    offset,
+                })?,
+            }
+        }
+        Ok(self.stack.pop().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tools::stress::Rng;
+
+    /// Random well-formed formula over `a b c`, stack words and assignments
+    /// included.
+    fn random_formula(rng: &mut Rng) -> String {
+        let mut tokens = Vec::new();
+        let mut depth = 0;
+        for _ in 0..rng.range(1, 30) {
+            let choice = rng.range(0, 9);
+            if depth >= 2 && choice < 4 {
+                tokens.push(*rng.pick(&["+", "-", "*", "/", "%", "swap", "over", "max"]));
+                depth -= 1;
+                if matches!(tokens.last(), Some(&"swap")) {
+                    depth += 1;
+                } else if matches!(tokens.last(), Some(&"over")) {
+                    depth += 2;
+                }
+            } else if depth >= 1 && choice == 4 {
+                tokens.push(*rng.pick(&["neg", "abs", "dup", "=c", "=t"]));
+                match tokens.last() {
+                    Some(&"dup") => depth += 1,
+                    Some(&"=c" | &"=t") => depth -= 1,
+                    _ => {}
+                }
+            } else {
+                tokens.push(*rng.pick(&["a", "b", "c", "t", "1", "2", "-7", "100"]));
+                depth += 1;
+            }
+        }
+        while depth > 1 {
+            tokens.push("+");
+            depth -= 1;
+        }
+        if depth == 0 {
+            tokens.push("a");
+        }
+        tokens.join(" ")
+    }
+
+    #[test]
+    fn agrees_with_the_formula_evaluator() {
+        let registry = Registry::standard();
+        let mut rng = Rng::new(19);
+        for _ in 0..2000 {
+            let expr = random_formula(&mut rng);
+            let formula = Formula::parse(&expr, &registry).unwrap();
+            let mut compiled = CompiledExpr::compile(&expr, &registry)
+                .unwrap_or_else(|e| panic!("{}: {}", expr, e));
+            for _ in 0..5 {
+                let bindings: Vec<i64> = compiled
+                    .inputs()
+                    .iter()
+                    .map(|_| rng.range(-1000, 1000))
+                    .collect();
+                let mut env: HashMap<String, i64> = compiled
+                    .inputs()
+                    .iter()
+                    .cloned()
+                    .zip(bindings.iter().copied())
+                    .collect();
+                assert_eq!(
+                    compiled.eval(&bindings),
+                    formula.eval(&mut env),
+                    "{} with {:?}",
+                    expr,
+                    bindings
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn evaluates_without_reallocating() {
+        let registry = Registry::standard();
+        let expr = "x dup over over * * =y y x over + swap - y 1 2 3 + + + * -";
+        let formula = Formula::parse(expr, &registry).unwrap();
+        let mut compiled = CompiledExpr::compile(expr, &registry).unwrap();
+        assert_eq!(compiled.inputs(), ["x"]);
+        let capacity = compiled.stack.capacity();
+        for x in -50..50 {
+            let mut env = HashMap::from([("x".to_string(), x)]);
+            assert_eq!(compiled.eval(&[x]), formula.eval(&mut env));
+            assert_eq!(compiled.stack.capacity(), capacity);
+        }
+    }
+
+    fn error(expr: &str) -> (ErrorKind, usize, usize) {
+        let registry = Registry::<i64>::standard();
+        let e = CompiledExpr::compile(expr, &registry).err().unwrap();
+        (e.kind, e.token, e.offset)
+    }
+
+    #[test]
+    #[should_panic(expected = "expected a binding for each of")]
+    fn every_input_needs_a_binding() {
+        let registry = Registry::standard();
+        let mut compiled = CompiledExpr::compile("a b +", &registry).unwrap();
+        let _ = compiled.eval(&[1]);
+    }
+
+    #[test]
+    fn stack_errors_are_found_when_compiling() {
+        assert_eq!(error("1 +"), (ErrorKind::StackUnderflow, 1, 2));
+        assert_eq!(error("x =y"), (ErrorKind::StackUnderflow, 2, 4));
+        assert_eq!(error("=y"), (ErrorKind::StackUnderflow, 0, 0));
+        assert_eq!(error("x dup 1"), (ErrorKind::LeftoverOperands(2), 1, 2));
+        assert_eq!(error("1 x"), (ErrorKind::LeftoverOperands(1), 0, 0));
+        assert_eq!(error("1 &"), (ErrorKind::UnknownToken("&".into()), 1, 2));
+
+        let registry = Registry::standard();
+        let mut compiled = CompiledExpr::compile("x 0 /", &registry).unwrap();
+        assert_eq!(
+            compiled.eval(&[1]).unwrap_err().kind,
+            ErrorKind::DivisionByZero
+        );
+        assert_eq!(compiled.eval(&[1]).unwrap_err().token, 2);
+    }
+}
@@ -0,0 +1,256 @@
+//! Symbolic differentiation of [`Expr`] trees.
+//!
+//! The derivative is built with the sum, product, quotient and power rules,
+//! leaving out the terms of subexpressions that do not mention the variable,
+//! and then simplified. Exponents must not mention the variable, `x ^ x`
+//! would need logarithms, and neither may remainders nor calls of other
+//! words, which have no rule.
+
+use crate::ast::Expr;
+use crate::postfix::{EvalError, Op, UnaryOp};
+use std::error::Error;
+use std::fmt;
+
+/// Why [`differentiate`] failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffError {
+    Parse(EvalError),
+    /// A power whose exponent mentions the variable.
+    VariableExponent(Expr),
+    /// A remainder or a call mentioning the variable.
+    NoRule(Expr),
+}
+
+impl fmt::Display for DiffError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiffError::Parse(e) => e.fmt(f),
+            DiffError::VariableExponent(power) => {
+                write!(f, "cannot differentiate `{}`, its exponent varies", power)
+            }
+            DiffError::NoRule(expr) => write!(f, "no rule to differentiate `{}`", expr),
+        }
+    }
+}
+
+impl Error for DiffError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DiffError::Parse(e) => Some(e),
+            DiffError::VariableExponent(_) | DiffError::NoRule(_) => None,
+        }
+    }
+}
+
+impl From<EvalError> for DiffError {
+    fn from(e: EvalError) -> Self {
+        DiffError::Parse(e)
+    }
+}
+
+fn node(op: Op, lhs: Expr, rhs: Expr) -> Expr {
+    Expr::Binary(op, Box::new(lhs), Box::new(rhs))
+}
+
+impl Expr {
+    /// Whether `var` occurs in the tree.
+    pub fn mentions(&self, var: &str) -> bool {
+        match self {
+            Expr::Value(_) => false,
+            Expr::Variable(name) => name == var,
+            Expr::Unary(_, operand) => operand.mentions(var),
+            Expr::Binary(_, lhs, rhs) => lhs.mentions(var) || rhs.mentions(var),
+            Expr::Call(_, operands) => operands.iter().any(|operand| operand.mentions(var)),
+        }
+    }
+
+    /// The simplified derivative with respect to `var`.
+    pub fn derivative(&self, var: &str) -> Result<Expr, DiffError> {
+        Ok(self.derive(var)?.unwrap_or(Expr::Value(0)).simplify())
+    }
+
+    /// `None` for a derivative of zero, so that products can drop the term.
+    fn derive(&self, var: &str) -> Result<Option<Expr>, DiffError> {
+        if !self.mentions(var) {
+            return Ok(None);
+        }
+        let (op, u, v) = match self {
+            Expr::Value(_) => unreachable!(),
+            Expr::Variable(_) => return Ok(Some(Expr::Value(1))),
+            Expr::Unary(UnaryOp::Negate, u) => {
+                let du = u.derive(var)?;
+                return Ok(du.map(|du| Expr::Unary(UnaryOp::Negate, Box::new(du))));
+            }
+            Expr::Call(..) => return Err(DiffError::NoRule(self.clone())),
+            Expr::Binary(op, u, v) => (*op, u.as_ref().clone(), v.as_ref().clone()),
+        };
+        let (du, dv) = (u.derive(var)?, v.derive(var)?);
+        if du.is_none() && dv.is_none() {
+            // both sides mention `var` only through constant powers
+            return Ok(None);
+        }
+        Ok(Some(match op {
+            Op::Add | Op::Subtract => match (du, dv) {
+                (Some(du), Some(dv)) => node(op, du, dv),
+                (Some(du), None) => du,
+                (None, Some(dv)) if op == Op::Add => dv,
+                (None, Some(dv)) => Expr::Unary(UnaryOp::Negate, Box::new(dv)),
+                (None, None) => unreachable!(),
+            },
+            // (uv)' = u'v + uv'
+            Op::Multiply => match (du, dv) {
+                (Some(du), Some(dv)) => node(Op::Add, node(op, du, v), node(op, u, dv)),
+                (Some(du), None) => node(op, du, v),
+                (None, Some(dv)) => node(op, u, dv),
+                (None, None) => unreachable!(),
+            },
+            // (u/v)' = (u'v - uv') / v^2
+            Op::Divide => {
+                let numerator = match (du, dv) {
+                    (Some(du), Some(dv)) => node(
+                        Op::Subtract,
+                        node(Op::Multiply, du, v.clone()),
+                        node(Op::Multiply, u, dv),
+                    ),
+                    (Some(du), None) => return Ok(Some(node(op, du, v))),
+                    (None, Some(dv)) => {
+                        Expr::Unary(UnaryOp::Negate, Box::new(node(Op::Multiply, u, dv)))
+                    }
+                    (None, None) => unreachable!(),
+                };
+                node(op, numerator, node(Op::Power, v, Expr::Value(2)))
+            }
+            Op::Remainder => return Err(DiffError::NoRule(self.clone())),
+            // (u^n)' = n u^(n-1) u'
+            Op::Power => {
+                let (Some(du), None) = (du, dv) else {
+                    return Err(DiffError::VariableExponent(self.clone()));
+                };
+                // u^0 is constant, and n u^(n-1) would not be defined at u = 0
+                if v.simplify() == Expr::Value(0) {
+                    return Ok(None);
+                }
+                let lowered = node(Op::Subtract, v.clone(), Expr::Value(1));
+                node(
+                    Op::Multiply,
+                    node(Op::Multiply, v, node(Op::Power, u, lowered)),
+                    du,
+                )
+            }
+        }))
+    }
+}
+
+/// The derivative of a postfix expression with respect to `var`.
+pub fn differentiate(expr: &str, var: &str) -> Result<Expr, DiffError> {
+    Expr::from_postfix(expr)?.derivative(var)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::random_tree;
+    use crate::formula::Formula;
+    use crate::registry::Registry;
+    use std::collections::HashMap;
+    use tools::stress::Rng;
+
+    fn derivative(expr: &str) -> String {
+        differentiate(expr, "x").unwrap().to_string()
+    }
+
+    #[test]
+    fn applies_the_rules() {
+        assert_eq!(derivative("3 x * 2 +"), "3");
+        assert_eq!(derivative("x 3 ^"), "3 * x ^ 2");
+        assert_eq!(derivative("x y *"), "y");
+        assert_eq!(derivative("x x *"), "x + x");
+        assert_eq!(derivative("1 x /"), "-1 / x ^ 2");
+        assert_eq!(derivative("x y /"), "1 / y");
+        assert_eq!(
+            derivative("x 2 ^ 1 x - /"),
+            "(2 * x * (1 - x) - x ^ 2 * -1) / (1 - x) ^ 2"
+        );
+        assert_eq!(derivative("y 5 -"), "0");
+        assert_eq!(derivative("x 0 ^"), "0");
+        assert_eq!(derivative("x 0 ^ x 0 ^ *"), "0");
+        assert_eq!(derivative("x 1 1 - ^ x *"), "x ^ 0");
+        assert_eq!(derivative("x neg 2 x * -"), "-3");
+        assert!(matches!(
+            differentiate("2 x ^", "x"),
+            Err(DiffError::VariableExponent(_))
+        ));
+        assert!(matches!(
+            differentiate("x 2 %", "x"),
+            Err(DiffError::NoRule(_))
+        ));
+        assert!(matches!(
+            differentiate("x abs", "x"),
+            Err(DiffError::NoRule(_))
+        ));
+        assert_eq!(derivative("y abs x *"), "abs(y)");
+        assert!(matches!(
+            differentiate("x +", "x"),
+            Err(DiffError::Parse(_))
+        ));
+    }
+
+    /// Compares with central differences in floating point, away from the
+    /// points where either difference is unstable.
+    #[test]
+    fn agrees_with_finite_differences() {
+        let registry = Registry::<f64>::standard();
+        let leaves: Vec<Expr> = ["x", "x", "x", "y"]
+            .iter()
+            .map(|name| Expr::Variable(name.to_string()))
+            .chain((-3..=3).map(Expr::Value))
+            .collect();
+        let ops = [Op::Add, Op::Subtract, Op::Multiply, Op::Divide, Op::Power];
+        let mut rng = Rng::new(21);
+        let mut checked = 0;
+        for _ in 0..2000 {
+            let tree = random_tree(&mut rng, 4, &leaves, &ops);
+            let function = Formula::parse(&tree.to_postfix(), &registry).unwrap();
+            let derivative = match tree.derivative("x") {
+                Ok(derivative) => derivative,
+                Err(DiffError::VariableExponent(_)) => continue,
+                Err(e) => panic!("d/dx {}: {}", tree, e),
+            };
+            let slope = Formula::parse(&derivative.to_postfix(), &registry).unwrap();
+            let at = |formula: &Formula<f64>, x: f64, y: f64| {
+                let mut env = HashMap::from([("x".to_string(), x), ("y".to_string(), y)]);
+                formula.eval(&mut env).ok()
+            };
+            let difference = |x: f64, y: f64, h: f64| {
+                Some((at(&function, x + h, y)? - at(&function, x - h, y)?) / (2. * h))
+            };
+            for _ in 0..5 {
+                let x = rng.range(-3000, 3000) as f64 / 1000.;
+                let y = rng.range(-3000, 3000) as f64 / 1000.;
+                let (Some(expected), Some(coarse), Some(found)) = (
+                    difference(x, y, 1e-5),
+                    difference(x, y, 1e-4),
+                    at(&slope, x, y),
+                ) else {
+                    continue;
+                };
+                let tolerance = 1e-4 * (1. + expected.abs());
+                if (expected - coarse).abs() > tolerance {
+                    continue;
+                }
+                assert!(
+                    (found - expected).abs() <= 10. * tolerance,
+                    "d/dx {} = {} is {} at x = {}, y = {}, expected {}",
+                    tree,
+                    derivative,
+                    found,
+                    x,
+                    y,
+                    expected
+                );
+                checked += 1;
+            }
+        }
+        assert!(checked > 5000, "only {} points checked", checked);
+    }
+}
@@ -0,0 +1,277 @@
+//! Postfix expressions with variables, parsed once and evaluated against any
+//! number of environments, as a spreadsheet does with a formula.
+//!
+//! A token that is neither a word of the [`Registry`] nor a number must be an
+//! identifier, letters, digits and `_` not starting with a digit, and pushes
+//! the value bound to it. `=name` pops the top of the stack into `name`, so
+//! `a b + =s s s *` squares a sum.
+
+use crate::numeric::Number;
+use crate::postfix::{tokens, ErrorKind, EvalError};
+use crate::registry::{Registry, Word};
+use std::collections::HashMap;
+
+/// Where identifiers are looked up and assignments are stored.
+pub trait Environment<N> {
+    fn get(&self, name: &str) -> Option<N>;
+    fn set(&mut self, name: &str, value: N);
+}
+
+impl<N: Clone> Environment<N> for HashMap<String, N> {
+    fn get(&self, name: &str) -> Option<N> {
+        HashMap::get(self, name).cloned()
+    }
+
+    fn set(&mut self, name: &str, value: N) {
+        self.insert(name.to_string(), value);
+    }
+}
+
+pub(crate) fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// A token evaluated without error, the `len` bytes at `offset` of the
+/// expression, stacks listed bottom first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Step<'a, N> {
+    pub token: usize,
+    pub offset: usize,
+    pub len: usize,
+    pub before: &'a [N],
+    pub after: &'a [N],
+}
+
+type Hook<'h, N> = dyn FnMut(&Step<N>) + 'h;
+
+pub(crate) enum Term<'r, N> {
+    Value(N),
+    Word(&'r Word<N>),
+    Load(String),
+    Store(String),
+}
+
+/// A parsed expression. Unknown tokens and malformed numbers are reported by
+/// [`Formula::parse`], everything else, unbound names included, by
+/// [`Formula::eval`].
+pub struct Formula<'r, N> {
+    /// Terms with their token index, byte offset and byte length.
+    pub(crate) terms: Vec<(Term<'r, N>, usize, usize, usize)>,
+    /// Where errors about the end of the expression point.
+    pub(crate) end: (usize, usize),
+}
+
+impl<'r, N: Number> Formula<'r, N> {
+    pub fn parse(expr: &str, registry: &'r Registry<N>) -> Result<Self, EvalError> {
+        let mut terms = Vec::new();
+        for (token, (offset, s)) in tokens(expr).enumerate() {
+            let term = if let Some(word) = registry.get(s) {
+                Term::Word(word)
+            } else if let Some(name) = s.strip_prefix('=') {
+                if !is_identifier(name) || registry.get(name).is_some() {
+                    return Err(EvalError {
+                        kind: ErrorKind::UnknownToken(s.to_string()),
+                        token,
+                        offset,
+                    });
+                }
+                Term::Store(name.to_string())
+            } else {
+                match N::parse(s) {
+                    Ok(value) => Term::Value(value),
+                    Err(_) if is_identifier(s) => Term::Load(s.to_string()),
+                    Err(kind) => {
+                        return Err(EvalError {
+                            kind,
+                            token,
+                            offset,
+                        })
+                    }
+                }
+            };
+            terms.push((term, token, offset, s.len()));
+        }
+        Ok(Formula {
+            end: (terms.len(), expr.len()),
+            terms,
+        })
+    }
+
+    /// The names read before they are assigned, which the environment must
+    /// bind, in order of first use.
+    pub fn inputs(&self) -> Vec<&str> {
+        let mut assigned = Vec::new();
+        let mut inputs = Vec::new();
+        for (term, ..) in &self.terms {
+            match term {
+                Term::Load(name)
+                    if !assigned.contains(name) && !inputs.contains(&name.as_str()) =>
+                {
+                    inputs.push(name.as_str())
+                }
+                Term::Store(name) => assigned.push(name.clone()),
+                _ => {}
+            }
+        }
+        inputs
+    }
+
+    pub fn eval<E: Environment<N>>(&self, env: &mut E) -> Result<N, EvalError> {
+        self.run(env, None)
+    }
+
+    /// Evaluates like [`Formula::eval`], showing `hook` every token that
+    /// succeeds with the stack before and after it.
+    pub fn eval_traced<E, F>(&self, env: &mut E, mut hook: F) -> Result<N, EvalError>
+    where
+        E: Environment<N>,
+        F: FnMut(&Step<N>),
+    {
+        self.run(env, Some(&mut hook))
+    }
+
+    fn run<E: Environment<N>>(
+        &self,
+        env: &mut E,
+        mut hook: Option<&mut Hook<N>>,
+    ) -> Result<N, EvalError> {
+        let mut stack: Vec<N> = Vec::new();
+        // the position of the token that produced each operand
+        let mut origins: Vec<(usize, usize)> = Vec::new();
+        let mut before: Vec<N> = Vec::new();
+        for &(ref term, token, offset, len) in &self.terms {
+            if hook.is_some() {
+                before.clone_from(&stack);
+            }
+            let error = |kind| EvalError {
+                kind,
+                token,
+                offset,
+            };
+            match term {
+                Term::Value(value) => stack.push(value.clone()),
+                Term::Word(word) => {
+                    word.apply(&mut stack).map_err(error)?;
+                    origins.truncate(origins.len() - word.arity);
+                }
+                Term::Load(name) => stack.push(
+                    env.get(name)
+                        .ok_or_else(|| error(ErrorKind::UnboundVariable(name.clone())))?,
+                ),
+                Term::Store(name) => {
+                    env.set(name, stack.pop().ok_or(error(ErrorKind::StackUnderflow))?);
+                    origins.pop();
+                }
+            }
+            origins.resize(stack.len(), (token, offset));
+            if let Some(hook) = hook.as_mut() {
+                hook(&Step {
+                    token,
+                    offset,
+                    len,
+                    before: &before,
+                    after: &stack,
+                });
+            }
+        }
+        match stack.len() {
+            0 => Err(EvalError {
+                kind: ErrorKind::StackUnderflow,
+                token: self.end.0,
+                offset: self.end.1,
+            }),
+            1 => Ok(stack.pop().unwrap()),
+            n => {
+                // only the top one is the result, point at the deepest of the others
+                let (token, offset) = origins[0];
+                Err(EvalError {
+                    kind: ErrorKind::LeftoverOperands(n - 1),
+                    token,
+                    offset,
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numeric::Rational;
+
+    fn bindings<N>(pairs: &[(&str, N)]) -> HashMap<String, N>
+    where
+        N: Clone,
+    {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn one_formula_many_bindings() {
+        let registry = Registry::standard();
+        let formula = Formula::parse("price qty * 100 discount - * 100 /", &registry).unwrap();
+        assert_eq!(formula.inputs(), ["price", "qty", "discount"]);
+        let rows = [(250, 4, 10, 900), (99, 3, 0, 297), (1000, 1, 100, 0)];
+        for (price, qty, discount, total) in rows {
+            let mut env = bindings(&[("price", price), ("qty", qty), ("discount", discount)]);
+            assert_eq!(formula.eval(&mut env), Ok(total));
+        }
+    }
+
+    #[test]
+    fn assignments() {
+        let registry = Registry::<Rational>::standard();
+        let formula = Formula::parse("a b + =s s s * =sq sq 2 /", &registry).unwrap();
+        assert_eq!(formula.inputs(), ["a", "b"]);
+        let mut env = bindings(&[("a", Rational::from_i64(1)), ("b", Rational::from_i64(2))]);
+        assert_eq!(formula.eval(&mut env).unwrap().to_string(), "9/2");
+        assert_eq!(env["s"], Rational::from_i64(3));
+        assert_eq!(env["sq"], Rational::from_i64(9));
+
+        // a later read sees the assignment, an earlier one the binding
+        let formula = Formula::parse("x 1 + =x x x *", &registry).unwrap();
+        assert_eq!(formula.inputs(), ["x"]);
+        let mut env = bindings(&[("x", Rational::from_i64(4))]);
+        assert_eq!(formula.eval(&mut env), Ok(Rational::from_i64(25)));
+    }
+
+    fn error(expr: &str, env: &[(&str, i64)]) -> (ErrorKind, usize, usize) {
+        let registry = Registry::standard();
+        let e = Formula::parse(expr, &registry)
+            .and_then(|formula| formula.eval(&mut bindings(env)))
+            .unwrap_err();
+        (e.kind, e.token, e.offset)
+    }
+
+    #[test]
+    fn errors_are_located() {
+        let unbound = ErrorKind::UnboundVariable("y".to_string());
+        assert_eq!(error("x y +", &[("x", 1)]), (unbound, 1, 2));
+        assert_eq!(error("=x", &[]), (ErrorKind::StackUnderflow, 0, 0));
+        assert_eq!(error("1 =x", &[]), (ErrorKind::StackUnderflow, 2, 4));
+        assert_eq!(
+            error("1 2 =x 3", &[]),
+            (ErrorKind::LeftoverOperands(1), 0, 0)
+        );
+        // parse errors come first, wherever they are
+        assert_eq!(
+            error("+ 1 2x", &[]),
+            (ErrorKind::UnknownToken("2x".to_string()), 2, 4)
+        );
+        assert_eq!(
+            error("1 =dup", &[]),
+            (ErrorKind::UnknownToken("=dup".to_string()), 1, 2)
+        );
+        assert_eq!(
+            error("1 =", &[]),
+            (ErrorKind::UnknownToken("=".to_string()), 1, 2)
+        );
+    }
+}
@@ -0,0 +1,339 @@
+//! Infix arithmetic, converted to postfix with the shunting-yard algorithm
+//! and evaluated by [`crate::postfix`].
+//!
+//! Unary minus comes out as the postfix `neg` word, `-(1 + 2)` as `1 2 + neg`.
+
+use crate::postfix::{self, EvalError};
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Number,
+    Operator,
+    Open,
+    Close,
+}
+
+/// A token of the infix input, or of the postfix output, where it keeps the
+/// position of the infix token it comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    /// 0-based index among the infix tokens.
+    pub index: usize,
+    /// Byte offset in the infix expression.
+    pub offset: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    UnexpectedChar(char),
+    /// An operator or parenthesis where it cannot go, such as `2 + * 3`.
+    UnexpectedToken(String),
+    /// The expression stops where an operand is still needed.
+    UnexpectedEnd,
+    UnmatchedOpen,
+    UnmatchedClose,
+}
+
+/// A malformed infix expression, located like [`EvalError`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub token: usize,
+    pub offset: usize,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "token {} (byte {}): ", self.token, self.offset)?;
+        match &self.kind {
+            ParseErrorKind::UnexpectedChar(c) => write!(f, "unexpected character `{}`", c),
+            ParseErrorKind::UnexpectedToken(t) => write!(f, "unexpected `{}`", t),
+            ParseErrorKind::UnexpectedEnd => f.write_str("unexpected end of expression"),
+            ParseErrorKind::UnmatchedOpen => f.write_str("unclosed `(`"),
+            ParseErrorKind::UnmatchedClose => f.write_str("unmatched `)`"),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// Why [`eval_infix`] failed; evaluation errors are located in the infix
+/// expression too.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InfixError {
+    Parse(ParseError),
+    Eval(EvalError),
+}
+
+impl fmt::Display for InfixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InfixError::Parse(e) => e.fmt(f),
+            InfixError::Eval(e) => e.fmt(f),
+        }
+    }
+}
+
+impl Error for InfixError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            InfixError::Parse(e) => Some(e),
+            InfixError::Eval(e) => Some(e),
+        }
+    }
+}
+
+impl From<ParseError> for InfixError {
+    fn from(e: ParseError) -> Self {
+        InfixError::Parse(e)
+    }
+}
+
+pub fn tokenize(expr: &str) -> Result<Vec<Token<'_>>, ParseError> {
+    let bytes = expr.as_bytes();
+    let mut tokens = Vec::new();
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let start = offset;
+        let kind = match bytes[offset] {
+            b if b.is_ascii_whitespace() => {
+                offset += 1;
+                continue;
+            }
+            b if b.is_ascii_digit() => {
+                while offset < bytes.len() && bytes[offset].is_ascii_digit() {
+                    offset += 1;
+                }
+                TokenKind::Number
+            }
+            b'+' | b'-' | b'*' | b'/' | b'%' | b'^' => TokenKind::Operator,
+            b'(' => TokenKind::Open,
+            b')' => TokenKind::Close,
+            _ => {
+                let c = expr[offset..].chars().next().unwrap_or_default();
+                return Err(ParseError {
+                    kind: ParseErrorKind::UnexpectedChar(c),
+                    token: tokens.len(),
+                    offset,
+                });
+            }
+        };
+        if kind != TokenKind::Number {
+            offset += 1;
+        }
+        tokens.push(Token {
+            kind,
+            text: &expr[start..offset],
+            index: tokens.len(),
+            offset: start,
+        });
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Assoc {
+    Left,
+    Right,
+}
+
+/// Precedence and associativity of a binary operator.
+fn binary(op: &str) -> (u8, Assoc) {
+    match op {
+        "^" => (4, Assoc::Right),
+        "*" | "/" | "%" => (2, Assoc::Left),
+        _ => (1, Assoc::Left),
+    }
+}
+
+/// Unary minus binds tighter than every binary operator but `^`, so that
+/// `-2^2` is -4.
+const NEGATION: (u8, Assoc) = (3, Assoc::Right);
+
+/// An operator waiting on the shunting-yard stack.
+struct Pending<'a> {
+    token: Token<'a>,
+    /// `None` for an opening parenthesis.
+    rank: Option<(u8, Assoc)>,
+}
+
+/// Reorders infix tokens into postfix.
+pub fn to_postfix<'a>(tokens: &[Token<'a>]) -> Result<Vec<Token<'a>>, ParseError> {
+    let mut output = Vec::new();
+    let mut stack: Vec<Pending<'a>> = Vec::new();
+    let mut expect_operand = true;
+    let unexpected = |token: &Token| ParseError {
+        kind: ParseErrorKind::UnexpectedToken(token.text.to_string()),
+        token: token.index,
+        offset: token.offset,
+    };
+
+    for &token in tokens {
+        match token.kind {
+            TokenKind::Number if expect_operand => {
+                output.push(token);
+                expect_operand = false;
+            }
+            TokenKind::Open if expect_operand => stack.push(Pending { token, rank: None }),
+            TokenKind::Close if !expect_operand => loop {
+                match stack.pop() {
+                    Some(Pending { rank: None, .. }) => break,
+                    Some(pending) => output.push(pending.token),
+                    None => {
+                        return Err(ParseError {
+                            kind: ParseErrorKind::UnmatchedClose,
+                            token: token.index,
+                            offset: token.offset,
+                        })
+                    }
+                }
+            },
+            TokenKind::Operator if expect_operand && token.text == "-" => {
+                stack.push(Pending {
+                    token: Token {
+                        text: "neg",
+                        ..token
+                    },
+                    rank: Some(NEGATION),
+                });
+            }
+            TokenKind::Operator if !expect_operand => {
+                let (precedence, assoc) = binary(token.text);
+                while let Some(&Pending {
+                    token: top,
+                    rank: Some((above, _)),
+                }) = stack.last()
+                {
+                    if above > precedence || (above == precedence && assoc == Assoc::Left) {
+                        output.push(top);
+                        stack.pop();
+                    } else {
+                        break;
+                    }
+                }
+                stack.push(Pending {
+                    token,
+                    rank: Some((precedence, assoc)),
+                });
+                expect_operand = true;
+            }
+            _ => return Err(unexpected(&token)),
+        }
+    }
+
+    if expect_operand {
+        return Err(ParseError {
+            kind: ParseErrorKind::UnexpectedEnd,
+            token: tokens.len(),
+            offset: tokens.last().map_or(0, |t| t.offset + t.text.len()),
+        });
+    }
+    while let Some(pending) = stack.pop() {
+        if pending.rank.is_none() {
+            return Err(ParseError {
+                kind: ParseErrorKind::UnmatchedOpen,
+                token: pending.token.index,
+                offset: pending.token.offset,
+            });
+        }
+        output.push(pending.token);
+    }
+    Ok(output)
+}
+
+/// Space separated postfix form of `expr`, as `postfix_evaluator` reads it.
+pub fn infix_to_postfix(expr: &str) -> Result<String, ParseError> {
+    let postfix = to_postfix(&tokenize(expr)?)?;
+    let texts: Vec<&str> = postfix.iter().map(|token| token.text).collect();
+    Ok(texts.join(" "))
+}
+
+pub fn eval_infix(expr: &str) -> Result<i64, InfixError> {
+    let tokens = tokenize(expr)?;
+    let postfix = to_postfix(&tokens)?;
+    let texts: Vec<&str> = postfix.iter().map(|token| token.text).collect();
+    postfix::try_postfix_evaluator(&texts.join(" ")).map_err(|e| {
+        // the evaluator counts postfix tokens, point back into `expr`
+        let (token, offset) = match postfix.get(e.token) {
+            Some(t) => (t.index, t.offset),
+            None => (tokens.len(), expr.len()),
+        };
+        InfixError::Eval(EvalError { token, offset, ..e })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::postfix::ErrorKind;
+
+    #[test]
+    fn converts_with_precedence_and_associativity() {
+        assert_eq!(
+            infix_to_postfix("(4 + 8) * (6 - 5) / ((3 - 2) * (2 + 2))").unwrap(),
+            "4 8 + 6 5 - * 3 2 - 2 2 + * /"
+        );
+        assert_eq!(infix_to_postfix("1+2*3").unwrap(), "1 2 3 * +");
+        assert_eq!(infix_to_postfix("8 - 3 - 2").unwrap(), "8 3 - 2 -");
+        assert_eq!(
+            infix_to_postfix("-3 * -(2 + 1)").unwrap(),
+            "3 neg 2 1 + neg *"
+        );
+        assert_eq!(infix_to_postfix("2 ^ 3 ^ 2").unwrap(), "2 3 2 ^ ^");
+        assert_eq!(infix_to_postfix("-2^2 * 3").unwrap(), "2 2 ^ neg 3 *");
+        assert_eq!(infix_to_postfix("2 ^ -1 ^ 2").unwrap(), "2 1 2 ^ neg ^");
+    }
+
+    #[test]
+    fn evaluates() {
+        assert_eq!(eval_infix("(4 + 8) * (6 - 5) / ((3 - 2) * (2 + 2))"), Ok(3));
+        assert_eq!(eval_infix("8 - 3 - 2"), Ok(3));
+        assert_eq!(eval_infix("16 / 4 / 2"), Ok(2));
+        assert_eq!(eval_infix("2 + 3 * 4 - 5"), Ok(9));
+        assert_eq!(eval_infix("--2"), Ok(2));
+        assert_eq!(eval_infix("-(2 + 3) * 2"), Ok(-10));
+        assert_eq!(eval_infix("7"), Ok(7));
+        assert_eq!(eval_infix("2 ^ 3 ^ 2"), Ok(512));
+        assert_eq!(eval_infix("-2 ^ 2"), Ok(-4));
+        assert_eq!(eval_infix("(-2) ^ 3 - 1"), Ok(-9));
+        assert_eq!(eval_infix("17 % 5 * 2"), Ok(4));
+    }
+
+    fn parse_error(expr: &str) -> (ParseErrorKind, usize, usize) {
+        match eval_infix(expr) {
+            Err(InfixError::Parse(e)) => (e.kind, e.token, e.offset),
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_errors_are_located() {
+        use ParseErrorKind::*;
+        assert_eq!(parse_error("2 + * 3"), (UnexpectedToken("*".into()), 2, 4));
+        assert_eq!(parse_error("2 3"), (UnexpectedToken("3".into()), 1, 2));
+        assert_eq!(parse_error("()"), (UnexpectedToken(")".into()), 1, 1));
+        assert_eq!(parse_error("1 $ 2"), (UnexpectedChar('$'), 1, 2));
+        assert_eq!(parse_error("(1 + 2"), (UnmatchedOpen, 0, 0));
+        assert_eq!(parse_error("1 + 2)"), (UnmatchedClose, 3, 5));
+        assert_eq!(parse_error("1 +"), (UnexpectedEnd, 2, 3));
+        assert_eq!(parse_error(""), (UnexpectedEnd, 0, 0));
+    }
+
+    #[test]
+    fn evaluation_errors_point_into_the_infix_expression() {
+        let e = eval_infix("1 + 6 / (2 - 2)").unwrap_err();
+        assert_eq!(
+            e,
+            InfixError::Eval(EvalError {
+                kind: ErrorKind::DivisionByZero,
+                token: 3,
+                offset: 6
+            })
+        );
+        assert_eq!(e.to_string(), "token 3 (byte 6): division by zero");
+    }
+}
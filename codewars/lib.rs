@@ -0,0 +1,39 @@
+//! Codewars katas and the expression toolkit grown around them.
+//!
+//! [`postfix`] and [`assembler`] are the kata submissions, `1.rs` and `2.rs`.
+//! The other modules build on the postfix evaluator: infix and prefix
+//! notations, expression trees with simplification and differentiation,
+//! operator registries, formulas with variables, compilation and tracing.
+//! [`calculator`] drives the `rpn` binary.
+
+#[path = "1.rs"]
+pub mod postfix;
+
+#[path = "2.rs"]
+pub mod assembler;
+
+pub mod infix;
+
+pub mod ast;
+
+pub mod prefix;
+
+pub mod notation;
+
+pub mod numeric;
+
+pub mod bigint;
+
+pub mod registry;
+
+pub mod formula;
+
+pub mod compiled;
+
+pub mod simplify;
+
+pub mod derivative;
+
+pub mod trace;
+
+pub mod calculator;
@@ -0,0 +1,85 @@
+//! Guessing whether an expression is written in prefix, postfix or infix
+//! notation, and evaluating it accordingly.
+
+use crate::infix::{self, InfixError};
+use crate::postfix::{self, ExprNode};
+use crate::prefix;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Notation {
+    Prefix,
+    Postfix,
+    Infix,
+}
+
+/// Parentheses or an operator between operands mean infix, otherwise a
+/// word of the standard registry in front means prefix and one at the end
+/// postfix. A lone number reads the same in all three and counts as postfix.
+pub fn detect(expr: &str) -> Notation {
+    if expr.contains(['(', ')']) {
+        return Notation::Infix;
+    }
+    postfix::with_standard(|registry| {
+        let is_word = |token: Option<&str>| {
+            token.is_some_and(|t| matches!(ExprNode::from_str(t, registry), Ok(ExprNode::Word(_))))
+        };
+        let mut tokens = expr.split_whitespace();
+        let (first, last) = (tokens.next(), tokens.next_back());
+        if is_word(first) {
+            Notation::Prefix
+        } else if is_word(last)
+            || (last.is_none() && first.is_some_and(|t| ExprNode::from_str(t, registry).is_ok()))
+        {
+            Notation::Postfix
+        } else {
+            Notation::Infix
+        }
+    })
+}
+
+/// Evaluates `expr` in the notation [`detect`] finds. Only infix input can
+/// fail with [`InfixError::Parse`].
+pub fn evaluate(expr: &str) -> Result<i64, InfixError> {
+    match detect(expr) {
+        Notation::Prefix => prefix::try_prefix_evaluator(expr).map_err(InfixError::Eval),
+        Notation::Postfix => postfix::try_postfix_evaluator(expr).map_err(InfixError::Eval),
+        Notation::Infix => infix::eval_infix(expr),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_the_notation() {
+        assert_eq!(detect("+ 2 * 3 4"), Notation::Prefix);
+        assert_eq!(detect("neg 3"), Notation::Prefix);
+        assert_eq!(detect("- 5 3"), Notation::Prefix);
+        assert_eq!(detect("2 3 4 * +"), Notation::Postfix);
+        assert_eq!(detect("-5 3 -"), Notation::Postfix);
+        assert_eq!(detect("42"), Notation::Postfix);
+        assert_eq!(detect("2 3 %"), Notation::Postfix);
+        assert_eq!(detect("3 dup *"), Notation::Postfix);
+        assert_eq!(detect("max 2 abs -3"), Notation::Prefix);
+        assert_eq!(detect("17 % 5"), Notation::Infix);
+        assert_eq!(detect("2 + 3 * 4"), Notation::Infix);
+        assert_eq!(detect("-5 - 3"), Notation::Infix);
+        assert_eq!(detect("2+3"), Notation::Infix);
+        assert_eq!(detect("(+ 2 3)"), Notation::Infix);
+        assert_eq!(detect(""), Notation::Infix);
+    }
+
+    #[test]
+    fn evaluates_every_notation() {
+        assert_eq!(evaluate("+ 2 * 3 4"), Ok(14));
+        assert_eq!(evaluate("2 3 4 * +"), Ok(14));
+        assert_eq!(evaluate("2 + 3 * 4"), Ok(14));
+        assert_eq!(evaluate("(2+3)*4"), Ok(20));
+        assert_eq!(evaluate("17 5 %"), Ok(2));
+        assert_eq!(evaluate("gcd 12 18"), Ok(6));
+        assert_eq!(evaluate("17 % 5"), Ok(2));
+        assert!(matches!(evaluate("2 +"), Err(InfixError::Eval(_))));
+        assert!(matches!(evaluate("2 + + 3"), Err(InfixError::Parse(_))));
+    }
+}
@@ -0,0 +1,408 @@
+//! Number types the evaluators can compute with.
+//!
+//! Every operation is checked: a result that cannot be represented is an
+//! [`ErrorKind::Overflow`] and a zero divisor an [`ErrorKind::DivisionByZero`],
+//! never a panic or a silent wrap. `i64` divides truncating toward zero like
+//! the kata expects, [`Rational`] and [`BigInt`](crate::bigint::BigInt) keep
+//! results exact, `f64` rounds.
+
+use crate::postfix::ErrorKind;
+use std::cmp::Ordering;
+use std::fmt;
+
+pub trait Number: Clone + fmt::Debug + fmt::Display + PartialOrd {
+    /// Reads a literal, `UnknownToken` when `token` is not a number.
+    fn parse(token: &str) -> Result<Self, ErrorKind>;
+    fn from_i64(n: i64) -> Self;
+    /// The value if it is an integer that fits in an `i64`.
+    fn to_i64(&self) -> Option<i64>;
+    fn add(&self, rhs: &Self) -> Result<Self, ErrorKind>;
+    fn sub(&self, rhs: &Self) -> Result<Self, ErrorKind>;
+    fn mul(&self, rhs: &Self) -> Result<Self, ErrorKind>;
+    fn div(&self, rhs: &Self) -> Result<Self, ErrorKind>;
+    /// Remainder of the truncated division, with the sign of `self`.
+    fn rem(&self, rhs: &Self) -> Result<Self, ErrorKind>;
+    fn neg(&self) -> Result<Self, ErrorKind>;
+
+    fn abs(&self) -> Result<Self, ErrorKind> {
+        if *self < Self::from_i64(0) {
+            self.neg()
+        } else {
+            Ok(self.clone())
+        }
+    }
+
+    /// `self` to a non-negative integer power.
+    fn pow(&self, exponent: &Self) -> Result<Self, ErrorKind> {
+        power(self, exponent)
+    }
+
+    /// Greatest common divisor of two integers, always non-negative.
+    fn gcd(&self, rhs: &Self) -> Result<Self, ErrorKind> {
+        let (zero, one) = (Self::from_i64(0), Self::from_i64(1));
+        if self.rem(&one)? != zero || rhs.rem(&one)? != zero {
+            return Err(ErrorKind::Domain("gcd of non-integers"));
+        }
+        let (mut a, mut b) = (self.abs()?, rhs.abs()?);
+        while b != zero {
+            (a, b) = (b.clone(), a.rem(&b)?);
+        }
+        Ok(a)
+    }
+}
+
+/// Integer power by repeated squaring, the default [`Number::pow`].
+pub fn power<N: Number>(base: &N, exponent: &N) -> Result<N, ErrorKind> {
+    let mut exponent = match exponent.to_i64() {
+        Some(e) if e >= 0 => e,
+        Some(_) => return Err(ErrorKind::Domain("negative exponent")),
+        None if exponent.rem(&N::from_i64(1))? == N::from_i64(0) => {
+            return Err(ErrorKind::Overflow)
+        }
+        None => return Err(ErrorKind::Domain("fractional exponent")),
+    };
+    let (mut base, mut power) = (base.clone(), N::from_i64(1));
+    while exponent > 0 {
+        if exponent % 2 == 1 {
+            power = power.mul(&base)?;
+        }
+        exponent /= 2;
+        if exponent > 0 {
+            base = base.mul(&base)?;
+        }
+    }
+    Ok(power)
+}
+
+/// An optionally signed run of ASCII digits.
+pub(crate) fn is_integer_literal(token: &str) -> bool {
+    let digits = token.strip_prefix(['-', '+']).unwrap_or(token);
+    !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn unknown(token: &str) -> ErrorKind {
+    ErrorKind::UnknownToken(token.to_string())
+}
+
+impl Number for i64 {
+    fn parse(token: &str) -> Result<Self, ErrorKind> {
+        match token.parse() {
+            Ok(n) => Ok(n),
+            Err(_) if is_integer_literal(token) => Err(ErrorKind::Overflow),
+            Err(_) => Err(unknown(token)),
+        }
+    }
+
+    fn from_i64(n: i64) -> Self {
+        n
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        Some(*self)
+    }
+
+    fn add(&self, rhs: &Self) -> Result<Self, ErrorKind> {
+        self.checked_add(*rhs).ok_or(ErrorKind::Overflow)
+    }
+
+    fn sub(&self, rhs: &Self) -> Result<Self, ErrorKind> {
+        self.checked_sub(*rhs).ok_or(ErrorKind::Overflow)
+    }
+
+    fn mul(&self, rhs: &Self) -> Result<Self, ErrorKind> {
+        self.checked_mul(*rhs).ok_or(ErrorKind::Overflow)
+    }
+
+    fn div(&self, rhs: &Self) -> Result<Self, ErrorKind> {
+        if *rhs == 0 {
+            return Err(ErrorKind::DivisionByZero);
+        }
+        self.checked_div(*rhs).ok_or(ErrorKind::Overflow)
+    }
+
+    fn rem(&self, rhs: &Self) -> Result<Self, ErrorKind> {
+        if *rhs == 0 {
+            return Err(ErrorKind::DivisionByZero);
+        }
+        // i64::MIN % -1 is 0, wrapping_rem does not trip on the overflowing
+        // quotient
+        Ok(self.wrapping_rem(*rhs))
+    }
+
+    fn neg(&self) -> Result<Self, ErrorKind> {
+        self.checked_neg().ok_or(ErrorKind::Overflow)
+    }
+}
+
+/// Infinite results count as overflow, so `f64` fails where the exact types
+/// would instead of carrying `inf` along.
+fn finite(x: f64) -> Result<f64, ErrorKind> {
+    if x.is_finite() {
+        Ok(x)
+    } else {
+        Err(ErrorKind::Overflow)
+    }
+}
+
+impl Number for f64 {
+    /// Decimal literals only, `inf` and `NaN` are not numbers here.
+    fn parse(token: &str) -> Result<Self, ErrorKind> {
+        let decimal = token.bytes().any(|b| b.is_ascii_digit())
+            && token
+                .bytes()
+                .all(|b| b.is_ascii_digit() || b"+-.eE".contains(&b));
+        match token.parse() {
+            Ok(x) if decimal => finite(x),
+            _ => Err(unknown(token)),
+        }
+    }
+
+    fn from_i64(n: i64) -> Self {
+        n as f64
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        // i64::MAX as f64 rounds up to 2^63, which is already out of range
+        let in_range = *self >= i64::MIN as f64 && *self < i64::MAX as f64;
+        (in_range && self.fract() == 0.).then_some(*self as i64)
+    }
+
+    fn add(&self, rhs: &Self) -> Result<Self, ErrorKind> {
+        finite(self + rhs)
+    }
+
+    fn sub(&self, rhs: &Self) -> Result<Self, ErrorKind> {
+        finite(self - rhs)
+    }
+
+    fn mul(&self, rhs: &Self) -> Result<Self, ErrorKind> {
+        finite(self * rhs)
+    }
+
+    fn div(&self, rhs: &Self) -> Result<Self, ErrorKind> {
+        if *rhs == 0. {
+            return Err(ErrorKind::DivisionByZero);
+        }
+        finite(self / rhs)
+    }
+
+    fn rem(&self, rhs: &Self) -> Result<Self, ErrorKind> {
+        if *rhs == 0. {
+            return Err(ErrorKind::DivisionByZero);
+        }
+        Ok(self % rhs)
+    }
+
+    fn neg(&self) -> Result<Self, ErrorKind> {
+        Ok(-self)
+    }
+
+    /// Any real exponent, as long as the result is a real number.
+    fn pow(&self, exponent: &Self) -> Result<Self, ErrorKind> {
+        let power = self.powf(*exponent);
+        if power.is_nan() {
+            return Err(ErrorKind::Domain("no real power"));
+        }
+        finite(power)
+    }
+}
+
+/// An exact fraction in lowest terms with a positive denominator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rational {
+    numerator: i64,
+    denominator: i64,
+}
+
+fn gcd(mut a: i128, mut b: i128) -> i128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a.abs()
+}
+
+impl Rational {
+    pub fn new(numerator: i64, denominator: i64) -> Result<Self, ErrorKind> {
+        Rational::reduce(numerator as i128, denominator as i128)
+    }
+
+    pub fn numerator(&self) -> i64 {
+        self.numerator
+    }
+
+    pub fn denominator(&self) -> i64 {
+        self.denominator
+    }
+
+    /// Normalizes a fraction computed in 128 bits, which holds any product
+    /// of two 64-bit values.
+    fn reduce(numerator: i128, denominator: i128) -> Result<Self, ErrorKind> {
+        if denominator == 0 {
+            return Err(ErrorKind::DivisionByZero);
+        }
+        let g = gcd(numerator, denominator) * denominator.signum();
+        let (numerator, denominator) = (numerator / g, denominator / g);
+        Ok(Rational {
+            numerator: numerator.try_into().map_err(|_| ErrorKind::Overflow)?,
+            denominator: denominator.try_into().map_err(|_| ErrorKind::Overflow)?,
+        })
+    }
+
+    fn parts(&self) -> (i128, i128) {
+        (self.numerator as i128, self.denominator as i128)
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let ((a, b), (c, d)) = (self.parts(), other.parts());
+        (a * d).cmp(&(c * b))
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.denominator == 1 {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
+
+impl Number for Rational {
+    /// Integers and decimals, `0.75` being exactly 3/4.
+    fn parse(token: &str) -> Result<Self, ErrorKind> {
+        let (whole, fraction) = token.split_once('.').unwrap_or((token, ""));
+        let digits = format!("{}{}", whole, fraction);
+        if !is_integer_literal(&digits) || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(unknown(token));
+        }
+        let numerator: i64 = digits.parse().map_err(|_| ErrorKind::Overflow)?;
+        let denominator = 10i64
+            .checked_pow(fraction.len() as u32)
+            .ok_or(ErrorKind::Overflow)?;
+        Rational::new(numerator, denominator)
+    }
+
+    fn from_i64(n: i64) -> Self {
+        Rational {
+            numerator: n,
+            denominator: 1,
+        }
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        (self.denominator == 1).then_some(self.numerator)
+    }
+
+    fn add(&self, rhs: &Self) -> Result<Self, ErrorKind> {
+        let ((a, b), (c, d)) = (self.parts(), rhs.parts());
+        Rational::reduce(a * d + c * b, b * d)
+    }
+
+    fn sub(&self, rhs: &Self) -> Result<Self, ErrorKind> {
+        let ((a, b), (c, d)) = (self.parts(), rhs.parts());
+        Rational::reduce(a * d - c * b, b * d)
+    }
+
+    fn mul(&self, rhs: &Self) -> Result<Self, ErrorKind> {
+        let ((a, b), (c, d)) = (self.parts(), rhs.parts());
+        Rational::reduce(a * c, b * d)
+    }
+
+    fn div(&self, rhs: &Self) -> Result<Self, ErrorKind> {
+        let ((a, b), (c, d)) = (self.parts(), rhs.parts());
+        Rational::reduce(a * d, b * c)
+    }
+
+    fn rem(&self, rhs: &Self) -> Result<Self, ErrorKind> {
+        let ((a, b), (c, d)) = (self.parts(), rhs.parts());
+        if c == 0 {
+            return Err(ErrorKind::DivisionByZero);
+        }
+        // a/b - q c/d with q the truncated quotient, |q c b| <= |a d|
+        let q = (a * d) / (b * c);
+        Rational::reduce(a * d - q * c * b, b * d)
+    }
+
+    fn neg(&self) -> Result<Self, ErrorKind> {
+        let (a, b) = self.parts();
+        Rational::reduce(-a, b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn i64_is_checked() {
+        assert_eq!(i64::parse("-12"), Ok(-12));
+        assert_eq!(i64::parse("99999999999999999999"), Err(ErrorKind::Overflow));
+        assert_eq!(i64::parse("1.5"), Err(unknown("1.5")));
+        assert_eq!(7.div(&-2), Ok(-3));
+        assert_eq!(i64::MIN.div(&-1), Err(ErrorKind::Overflow));
+        assert_eq!(1.div(&0), Err(ErrorKind::DivisionByZero));
+        assert_eq!((-7).rem(&2), Ok(-1));
+        assert_eq!(i64::MIN.rem(&-1), Ok(0));
+        assert_eq!(Number::pow(&2i64, &62), Ok(1 << 62));
+        assert_eq!(Number::pow(&2i64, &63), Err(ErrorKind::Overflow));
+        assert_eq!(Number::pow(&2i64, &-1), Err(ErrorKind::Domain("negative exponent")));
+        assert_eq!((-12).gcd(&18), Ok(6));
+        assert_eq!(0.gcd(&0), Ok(0));
+        assert_eq!(Number::abs(&i64::MIN), Err(ErrorKind::Overflow));
+    }
+
+    #[test]
+    fn f64_rejects_non_finite_values() {
+        assert_eq!(f64::parse("2.5e1"), Ok(25.));
+        assert_eq!(f64::parse("inf"), Err(unknown("inf")));
+        assert_eq!(f64::parse("-"), Err(unknown("-")));
+        assert_eq!(1e300.mul(&1e300), Err(ErrorKind::Overflow));
+        assert_eq!(1f64.div(&0.), Err(ErrorKind::DivisionByZero));
+        assert_eq!(2f64.pow(&0.5), Ok(2f64.sqrt()));
+        assert_eq!(
+            (-8f64).pow(&(1. / 3.)),
+            Err(ErrorKind::Domain("no real power"))
+        );
+        assert_eq!(12f64.gcd(&18.), Ok(6.));
+        assert_eq!(
+            1.5f64.gcd(&3.),
+            Err(ErrorKind::Domain("gcd of non-integers"))
+        );
+        assert_eq!(1e300.to_i64(), None);
+    }
+
+    #[test]
+    fn rationals_stay_in_lowest_terms() {
+        let r = |n, d| Rational::new(n, d).unwrap();
+        assert_eq!(r(6, -4), r(-3, 2));
+        assert_eq!((r(6, -4).numerator(), r(6, -4).denominator()), (-3, 2));
+        assert_eq!(r(1, 3).add(&r(1, 6)), Ok(r(1, 2)));
+        assert_eq!(r(1, 3).sub(&r(1, 3)), Ok(r(0, 1)));
+        assert_eq!(r(2, 3).div(&r(4, 9)), Ok(r(3, 2)));
+        assert_eq!(r(1, 2).div(&r(0, 5)), Err(ErrorKind::DivisionByZero));
+        assert_eq!(Rational::parse("0.75"), Ok(r(3, 4)));
+        assert_eq!(Rational::parse("-1.50"), Ok(r(-3, 2)));
+        assert_eq!(Rational::parse("1.2.3"), Err(unknown("1.2.3")));
+        assert_eq!(r(i64::MAX, 1).add(&r(1, 1)), Err(ErrorKind::Overflow));
+        assert_eq!(r(-7, 3).to_string(), "-7/3");
+        assert_eq!(r(4, 2).to_string(), "2");
+        assert_eq!(r(7, 2).rem(&r(1, 1)), Ok(r(1, 2)));
+        assert_eq!(r(-7, 2).rem(&r(3, 4)), Ok(r(-1, 2)));
+        assert_eq!(r(2, 3).pow(&r(3, 1)), Ok(r(8, 27)));
+        assert_eq!(
+            r(2, 3).pow(&r(1, 2)),
+            Err(ErrorKind::Domain("fractional exponent"))
+        );
+        assert!(r(1, 3) < r(1, 2) && r(-1, 2) < r(-1, 3));
+        assert_eq!(r(4, 1).gcd(&r(6, 1)), Ok(r(2, 1)));
+    }
+}
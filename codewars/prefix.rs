@@ -0,0 +1,119 @@
+//! Polish (prefix) notation, `+ 2 * 3 4`, evaluated with the function words
+//! of a [`Registry`], `max 2 abs -3` included. The stack words are postfix
+//! only.
+//!
+//! Tokens are read right to left onto an explicit stack, the mirror image of
+//! the postfix evaluator, so nesting depth is only bounded by memory.
+
+use crate::numeric::Number;
+use crate::postfix::{self, tokens, ErrorKind, EvalError, ExprNode};
+use crate::registry::Registry;
+
+pub fn try_prefix_evaluator(expr: &str) -> Result<i64, EvalError> {
+    postfix::with_standard(|registry| evaluate_with(expr, registry))
+}
+
+/// Evaluates with any [`Number`] type, see [`crate::postfix::evaluate`].
+pub fn evaluate<N: Number>(expr: &str) -> Result<N, EvalError> {
+    evaluate_with(expr, &Registry::standard())
+}
+
+/// Evaluates with the words of `registry`, any other token must be a number.
+pub fn evaluate_with<N: Number>(expr: &str, registry: &Registry<N>) -> Result<N, EvalError> {
+    let tokens: Vec<(usize, &str)> = tokens(expr).collect();
+    // operands with the position of the token that produced them
+    let mut stack: Vec<(N, usize, usize)> = Vec::new();
+    let mut operands = Vec::new();
+    for (token, &(offset, s)) in tokens.iter().enumerate().rev() {
+        let error = |kind| EvalError {
+            kind,
+            token,
+            offset,
+        };
+        let value = match ExprNode::from_str(s, registry).map_err(error)? {
+            ExprNode::Value(v) => v,
+            ExprNode::Word(word) => {
+                let function = ExprNode::function(word, s).map_err(error)?;
+                let Some(base) = stack.len().checked_sub(word.arity) else {
+                    return Err(error(ErrorKind::StackUnderflow));
+                };
+                // the leftmost operand was pushed last
+                operands.clear();
+                operands.extend(stack.drain(base..).rev().map(|(v, _, _)| v));
+                function(&operands).map_err(error)?
+            }
+        };
+        stack.push((value, token, offset));
+    }
+    match stack.len() {
+        0 => Err(EvalError {
+            kind: ErrorKind::StackUnderflow,
+            token: 0,
+            offset: expr.len(),
+        }),
+        1 => Ok(stack.pop().unwrap().0),
+        n => {
+            // the top is the result, point at the first operand after it
+            let (_, token, offset) = stack[n - 2];
+            Err(EvalError {
+                kind: ErrorKind::LeftoverOperands(n - 1),
+                token,
+                offset,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluates() {
+        assert_eq!(try_prefix_evaluator("+ 2 * 3 4"), Ok(14));
+        assert_eq!(try_prefix_evaluator("- 5 3"), Ok(2));
+        assert_eq!(try_prefix_evaluator("/ 8 2"), Ok(4));
+        assert_eq!(try_prefix_evaluator("* neg 2 - 1 -3"), Ok(-8));
+        assert_eq!(try_prefix_evaluator("-7"), Ok(-7));
+        assert_eq!(try_prefix_evaluator("/ * - 6 5 + 8 4 * - 3 2 + 2 2"), Ok(3));
+        assert_eq!(try_prefix_evaluator("% 17 5"), Ok(2));
+        assert_eq!(try_prefix_evaluator("max 2 abs -3"), Ok(3));
+        assert_eq!(try_prefix_evaluator("- gcd 12 18 min 4 1"), Ok(5));
+    }
+
+    #[test]
+    fn user_defined_words() {
+        let mut registry = Registry::<i64>::standard();
+        registry.define("clamp", 3, |a| Ok(a[0].clamp(a[1], a[2])));
+        assert_eq!(evaluate_with("clamp 15 0 10", &registry), Ok(10));
+        assert_eq!(evaluate_with("clamp -5 0 10", &registry), Ok(0));
+    }
+
+    #[test]
+    fn deep_nesting_does_not_overflow_the_stack() {
+        let depth = 200_000;
+        let expr = "+ 1 ".repeat(depth) + "0";
+        assert_eq!(try_prefix_evaluator(&expr), Ok(depth as i64));
+    }
+
+    fn error(expr: &str) -> (ErrorKind, usize, usize) {
+        let e = try_prefix_evaluator(expr).unwrap_err();
+        (e.kind, e.token, e.offset)
+    }
+
+    #[test]
+    fn errors_are_located() {
+        assert_eq!(error("+ 1"), (ErrorKind::StackUnderflow, 0, 0));
+        assert_eq!(error(""), (ErrorKind::StackUnderflow, 0, 0));
+        assert_eq!(error("+ 1 2 3"), (ErrorKind::LeftoverOperands(1), 3, 6));
+        assert_eq!(error("/ 1 - 2 2"), (ErrorKind::DivisionByZero, 0, 0));
+        assert_eq!(
+            error("+ 1 x"),
+            (ErrorKind::UnknownToken("x".to_string()), 2, 4)
+        );
+        assert_eq!(
+            error("* dup 3"),
+            (ErrorKind::StackWord("dup".to_string()), 1, 2)
+        );
+    }
+}
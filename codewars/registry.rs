@@ -0,0 +1,193 @@
+//! The words of the postfix language.
+//!
+//! Every word pops a fixed number of operands, its arity, and either computes
+//! one value from them or pushes some of them back in a new order, which is
+//! how the Forth-style stack words are written. New words are added with
+//! [`Registry::define`] and [`Registry::define_shuffle`], the evaluators only
+//! look names up.
+//!
+//! Every notation reads the same words, but the stack words are postfix
+//! only: prefix evaluation and [`Expr`](crate::ast::Expr) trees have no
+//! stack to shuffle and reject them with [`ErrorKind::StackWord`].
+
+use crate::numeric::Number;
+use crate::postfix::{ErrorKind, Op, UnaryOp};
+use std::collections::HashMap;
+
+pub type Function<N> = Box<dyn Fn(&[N]) -> Result<N, ErrorKind>>;
+
+pub enum Body<N> {
+    /// Computes one value from the operands, deepest first.
+    Function(Function<N>),
+    /// Pushes back the operands at these indices, deepest first: `over` is
+    /// `[0, 1, 0]`.
+    Shuffle(&'static [usize]),
+}
+
+pub struct Word<N> {
+    pub arity: usize,
+    pub body: Body<N>,
+}
+
+impl<N: Number> Word<N> {
+    /// Replaces the top `arity` values of `stack` with the results.
+    pub fn apply(&self, stack: &mut Vec<N>) -> Result<(), ErrorKind> {
+        let Some(base) = stack.len().checked_sub(self.arity) else {
+            return Err(ErrorKind::StackUnderflow);
+        };
+        match &self.body {
+            Body::Function(function) => {
+                let value = function(&stack[base..])?;
+                stack.truncate(base);
+                stack.push(value);
+            }
+            Body::Shuffle(pattern) => {
+                // copies on top, then the operands out from under them, so
+                // nothing is allocated once the stack has room
+                for &i in pattern.iter() {
+                    stack.push(stack[base + i].clone());
+                }
+                stack.drain(base..base + self.arity);
+            }
+        }
+        Ok(())
+    }
+
+    /// How many values the word leaves in place of its operands.
+    pub fn results(&self) -> usize {
+        match &self.body {
+            Body::Function(_) => 1,
+            Body::Shuffle(pattern) => pattern.len(),
+        }
+    }
+}
+
+pub struct Registry<N> {
+    words: HashMap<String, Word<N>>,
+}
+
+impl<N: Number> Default for Registry<N> {
+    fn default() -> Self {
+        Registry::standard()
+    }
+}
+
+impl<N: Number> Registry<N> {
+    /// No words at all, not even arithmetic.
+    pub fn empty() -> Self {
+        Registry {
+            words: HashMap::new(),
+        }
+    }
+
+    /// Arithmetic `+ - * / % ^`, `neg` and `abs`, the binary functions
+    /// `min`, `max` and `gcd`, and the stack words `dup swap drop over`.
+    pub fn standard() -> Self {
+        let mut registry = Self::empty();
+        for op in Op::ALL {
+            registry.define(op.symbol(), 2, move |a| op.apply(&a[0], &a[1]));
+        }
+        registry.define(UnaryOp::Negate.symbol(), 1, |a| {
+            UnaryOp::Negate.apply(&a[0])
+        });
+        registry.define("abs", 1, |a| a[0].abs());
+        registry.define("min", 2, |a| {
+            Ok(if a[1] < a[0] { &a[1] } else { &a[0] }.clone())
+        });
+        registry.define("max", 2, |a| {
+            Ok(if a[1] > a[0] { &a[1] } else { &a[0] }.clone())
+        });
+        registry.define("gcd", 2, |a| a[0].gcd(&a[1]));
+        registry.define_shuffle("dup", 1, &[0, 0]);
+        registry.define_shuffle("swap", 2, &[1, 0]);
+        registry.define_shuffle("drop", 1, &[]);
+        registry.define_shuffle("over", 2, &[0, 1, 0]);
+        registry
+    }
+
+    /// Adds or replaces a word computing one value from `arity` operands.
+    pub fn define<F>(&mut self, name: &str, arity: usize, function: F)
+    where
+        F: Fn(&[N]) -> Result<N, ErrorKind> + 'static,
+    {
+        let body = Body::Function(Box::new(function));
+        self.words.insert(name.to_string(), Word { arity, body });
+    }
+
+    /// Adds or replaces a stack word, see [`Body::Shuffle`].
+    pub fn define_shuffle(&mut self, name: &str, arity: usize, pattern: &'static [usize]) {
+        assert!(
+            pattern.iter().all(|&i| i < arity),
+            "`{}` shuffles operands it does not take",
+            name
+        );
+        let body = Body::Shuffle(pattern);
+        self.words.insert(name.to_string(), Word { arity, body });
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Word<N>> {
+        self.words.get(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numeric::Rational;
+    use crate::postfix::{evaluate_with, EvalError};
+
+    fn eval(expr: &str) -> Result<i64, EvalError> {
+        evaluate_with(expr, &Registry::standard())
+    }
+
+    #[test]
+    fn operators_and_functions() {
+        assert_eq!(eval("17 5 %"), Ok(2));
+        assert_eq!(eval("2 10 ^"), Ok(1024));
+        assert_eq!(eval("-3 abs 4 neg +"), Ok(-1));
+        assert_eq!(eval("3 9 min 4 max"), Ok(4));
+        assert_eq!(eval("12 18 gcd"), Ok(6));
+        assert_eq!(eval("2 64 ^").unwrap_err().kind, ErrorKind::Overflow);
+        assert_eq!(eval("5 0 %").unwrap_err().kind, ErrorKind::DivisionByZero);
+        let e = eval("2 -1 ^").unwrap_err();
+        assert_eq!(
+            (e.kind, e.token),
+            (ErrorKind::Domain("negative exponent"), 2)
+        );
+    }
+
+    #[test]
+    fn stack_words() {
+        assert_eq!(eval("3 dup *"), Ok(9));
+        assert_eq!(eval("5 3 swap -"), Ok(-2));
+        assert_eq!(eval("1 2 drop"), Ok(1));
+        assert_eq!(eval("5 3 over - *"), Ok(-10));
+        let e = eval("1 swap").unwrap_err();
+        assert_eq!(
+            (e.kind, e.token, e.offset),
+            (ErrorKind::StackUnderflow, 1, 2)
+        );
+        assert_eq!(
+            eval("1 2 dup").unwrap_err().kind,
+            ErrorKind::LeftoverOperands(2)
+        );
+    }
+
+    #[test]
+    fn user_defined_words() {
+        let mut registry = Registry::<Rational>::standard();
+        registry.define("avg3", 3, |a| {
+            a[0].add(&a[1])?.add(&a[2])?.div(&Rational::from_i64(3))
+        });
+        registry.define_shuffle("rot", 3, &[1, 2, 0]);
+        assert_eq!(
+            evaluate_with("1 2 4 avg3", &registry).unwrap().to_string(),
+            "7/3"
+        );
+        assert_eq!(
+            evaluate_with("1 2 3 rot - -", &registry),
+            Ok(Rational::from_i64(0))
+        );
+        assert!(evaluate_with::<i64>("1 2 +", &Registry::empty()).is_err());
+    }
+}
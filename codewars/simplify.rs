@@ -0,0 +1,203 @@
+//! Constant folding and identity elimination on [`Expr`] trees.
+//!
+//! A rewrite never changes what evaluating the expression gives, error
+//! included: an operation that fails on its constants, division by zero or
+//! overflow, is left for evaluation to report. Inexact divisions are not
+//! folded either, so that the tree keeps its meaning with exact arithmetic.
+//!
+//! `x - x`, `x * 0` and `x ^ 0` would drop `x`, and with it the error of
+//! an unbound `x`. [`Expr::simplify`] keeps them, [`Expr::simplify_bound`]
+//! rewrites them when `x` is a variable, for callers that bind every
+//! variable before evaluating.
+
+use crate::ast::Expr;
+use crate::postfix::{EvalError, Op, UnaryOp};
+
+impl Expr {
+    /// Simplifies bottom-up, `2 3 + x *` gives `5 x *`.
+    pub fn simplify(&self) -> Expr {
+        self.rewrite(false)
+    }
+
+    /// [`Expr::simplify`], also dropping variables that cannot change the
+    /// result once bound, `x x - y +` gives `y`.
+    pub fn simplify_bound(&self) -> Expr {
+        self.rewrite(true)
+    }
+
+    fn rewrite(&self, bound: bool) -> Expr {
+        match self {
+            Expr::Value(_) | Expr::Variable(_) => self.clone(),
+            Expr::Unary(op, operand) => unary(*op, operand.rewrite(bound)),
+            Expr::Binary(op, lhs, rhs) => {
+                binary(*op, lhs.rewrite(bound), rhs.rewrite(bound), bound)
+            }
+            // the tree does not know what the word computes
+            Expr::Call(name, operands) => Expr::Call(
+                name.clone(),
+                operands
+                    .iter()
+                    .map(|operand| operand.rewrite(bound))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+fn unary(op: UnaryOp, operand: Expr) -> Expr {
+    match operand {
+        Expr::Value(v) if op.apply(&v).is_ok() => Expr::Value(op.apply(&v).unwrap()),
+        operand => Expr::Unary(op, Box::new(operand)),
+    }
+}
+
+/// `bound` allows the rewrites that drop a variable.
+fn binary(op: Op, lhs: Expr, rhs: Expr, bound: bool) -> Expr {
+    if let (Expr::Value(a), Expr::Value(b)) = (&lhs, &rhs) {
+        let exact = op != Op::Divide || a.checked_rem(*b) == Some(0);
+        match op.apply(a, b) {
+            Ok(v) if exact => return Expr::Value(v),
+            _ => {}
+        }
+    }
+    match (op, lhs, rhs) {
+        (Op::Add, x, Expr::Value(0))
+        | (Op::Add, Expr::Value(0), x)
+        | (Op::Subtract, x, Expr::Value(0))
+        | (Op::Multiply, x, Expr::Value(1))
+        | (Op::Multiply, Expr::Value(1), x)
+        | (Op::Divide, x, Expr::Value(1))
+        | (Op::Power, x, Expr::Value(1)) => x,
+        // both overflow on i64::MIN alone
+        (Op::Subtract, Expr::Value(0), x) => unary(UnaryOp::Negate, x),
+        (Op::Subtract, Expr::Variable(x), Expr::Variable(y)) if bound && x == y => Expr::Value(0),
+        (Op::Multiply, Expr::Variable(_), Expr::Value(0))
+        | (Op::Multiply, Expr::Value(0), Expr::Variable(_))
+            if bound =>
+        {
+            Expr::Value(0)
+        }
+        (Op::Power, Expr::Variable(_), Expr::Value(0)) if bound => Expr::Value(1),
+        (op, lhs, rhs) => Expr::Binary(op, Box::new(lhs), Box::new(rhs)),
+    }
+}
+
+/// Simplifies a postfix expression and writes it back as postfix.
+pub fn simplify_postfix(expr: &str) -> Result<String, EvalError> {
+    Ok(Expr::from_postfix(expr)?.simplify().to_postfix())
+}
+
+/// [`simplify_postfix`] with [`Expr::simplify_bound`], for expressions whose
+/// variables are all bound before evaluating.
+pub fn simplify_bound_postfix(expr: &str) -> Result<String, EvalError> {
+    Ok(Expr::from_postfix(expr)?.simplify_bound().to_postfix())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::random_tree;
+    use crate::formula::Formula;
+    use crate::registry::Registry;
+    use std::collections::HashMap;
+    use tools::stress::Rng;
+
+    fn simplified(expr: &str) -> String {
+        simplify_postfix(expr).unwrap()
+    }
+
+    fn simplified_bound(expr: &str) -> String {
+        simplify_bound_postfix(expr).unwrap()
+    }
+
+    #[test]
+    fn folds_constants() {
+        assert_eq!(simplified("2 3 + x *"), "5 x *");
+        assert_eq!(simplified("x 2 3 * 4 - /"), "x 2 /");
+        assert_eq!(simplified("7 neg x +"), "-7 x +");
+        assert_eq!(simplified("x 1 0 / +"), "x 1 0 / +");
+        assert_eq!(simplified("x 7 2 / * 8 2 / +"), "x 7 2 / * 4 +");
+        assert_eq!(
+            simplified("9223372036854775807 1 + 0 *"),
+            "9223372036854775807 1 + 0 *"
+        );
+        assert_eq!(
+            simplified("-9223372036854775808 neg"),
+            "-9223372036854775808 neg"
+        );
+    }
+
+    #[test]
+    fn eliminates_identities() {
+        assert_eq!(simplified("x 1 * 0 +"), "x");
+        assert_eq!(simplified("0 x y / +"), "x y /");
+        assert_eq!(simplified("x 3 3 - - 2 1 - /"), "x");
+        assert_eq!(simplified("0 x -"), "x neg");
+        assert_eq!(simplified("x 1 ^"), "x");
+    }
+
+    #[test]
+    fn drops_variables_only_when_bound() {
+        for (expr, kept, dropped) in [
+            ("x x - y +", "x x - y +", "y"),
+            ("y y - x +", "y y - x +", "x"),
+            ("0 y * x +", "0 y * x +", "x"),
+            ("x 1 ^ 0 y * +", "x 0 y * +", "x"),
+            ("x 0 ^ x 1 + 0 ^ +", "x 0 ^ x 1 + 0 ^ +", "1 x 1 + 0 ^ +"),
+        ] {
+            assert_eq!(simplified(expr), kept);
+            assert_eq!(simplified_bound(expr), dropped);
+        }
+        // either side may fail
+        assert_eq!(simplified_bound("x y / x y / -"), "x y / x y / -");
+        assert_eq!(simplified_bound("x y -"), "x y -");
+    }
+
+    /// `unbound` leaves some of the variables out of the environment, which
+    /// only [`Expr::simplify`] has to cope with.
+    fn agrees_with_the_original(simplify: fn(&Expr) -> Expr, unbound: bool, seed: u64) {
+        let registry = Registry::standard();
+        let leaves: Vec<Expr> = ["a", "b", "c"]
+            .iter()
+            .map(|name| Expr::Variable(name.to_string()))
+            .chain([0, 1, 2, -1, 7, i64::MAX, i64::MIN].map(Expr::Value))
+            .collect();
+        let ops = [Op::Add, Op::Subtract, Op::Multiply, Op::Divide, Op::Power];
+        let mut rng = Rng::new(seed);
+        for _ in 0..3000 {
+            let tree = random_tree(&mut rng, 5, &leaves, &ops);
+            let (original, simplified) = (tree.to_postfix(), simplify(&tree).to_postfix());
+            let original = Formula::parse(&original, &registry).unwrap();
+            let simplified = Formula::parse(&simplified, &registry).unwrap();
+            for _ in 0..5 {
+                let mut env: HashMap<String, i64> = ["a", "b", "c"]
+                    .iter()
+                    .filter_map(|name| {
+                        let value = *rng.pick(&[0, 1, -1, 3, i64::MAX, i64::MIN]);
+                        let bound = !unbound || rng.range(0, 2) > 0;
+                        bound.then(|| (name.to_string(), value))
+                    })
+                    .collect();
+                let expected = original.eval(&mut env).map_err(|e| e.kind);
+                assert_eq!(
+                    simplified.eval(&mut env).map_err(|e| e.kind),
+                    expected,
+                    "{} simplified to {} with {:?}",
+                    tree,
+                    simplify(&tree),
+                    env
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn evaluation_agrees_with_the_original() {
+        agrees_with_the_original(Expr::simplify, true, 20);
+    }
+
+    #[test]
+    fn evaluation_agrees_with_the_original_when_bound() {
+        agrees_with_the_original(Expr::simplify_bound, false, 21);
+    }
+}
@@ -0,0 +1,157 @@
+//! Step by step evaluation, for finding out where an expression goes wrong.
+//!
+//! [`Formula::eval_traced`] hands every evaluated token to a callback, which
+//! is what a debugger front end hooks into. [`trace`] collects the steps
+//! into a [`Trace`], whose `Display` is an aligned table:
+//!
+//! ```text
+//! #  token  before  after
+//! 0  2              2
+//! 1  3      2       2 3
+//! 2  +      2 3     5
+//! result 5
+//! ```
+
+use crate::formula::{Environment, Formula};
+use crate::numeric::Number;
+use crate::postfix::EvalError;
+use crate::registry::Registry;
+use std::collections::HashMap;
+use std::fmt;
+
+pub use crate::formula::Step;
+
+/// An owned [`Step`], with the text of its token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row<N> {
+    pub token: usize,
+    pub offset: usize,
+    pub text: String,
+    pub before: Vec<N>,
+    pub after: Vec<N>,
+}
+
+/// The steps of an evaluation and how it ended. A failing token has no row,
+/// the error says which one it is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trace<N> {
+    pub rows: Vec<Row<N>>,
+    pub result: Result<N, EvalError>,
+}
+
+/// Evaluates `expr` with `registry` and `env`, recording every step.
+pub fn trace<N, E>(expr: &str, registry: &Registry<N>, env: &mut E) -> Trace<N>
+where
+    N: Number,
+    E: Environment<N>,
+{
+    let mut rows = Vec::new();
+    let result = Formula::parse(expr, registry).and_then(|formula| {
+        formula.eval_traced(env, |step| {
+            rows.push(Row {
+                token: step.token,
+                offset: step.offset,
+                text: expr[step.offset..step.offset + step.len].to_string(),
+                before: step.before.to_vec(),
+                after: step.after.to_vec(),
+            })
+        })
+    });
+    Trace { rows, result }
+}
+
+/// [`trace`] with the standard words and no variables.
+pub fn trace_postfix(expr: &str) -> Trace<i64> {
+    trace(expr, &Registry::standard(), &mut HashMap::new())
+}
+
+fn stack<N: fmt::Display>(values: &[N]) -> String {
+    let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+    values.join(" ")
+}
+
+impl<N: fmt::Display> fmt::Display for Trace<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut cells = vec![[
+            "#".to_string(),
+            "token".to_string(),
+            "before".to_string(),
+            "after".to_string(),
+        ]];
+        cells.extend(self.rows.iter().map(|row| {
+            [
+                row.token.to_string(),
+                row.text.clone(),
+                stack(&row.before),
+                stack(&row.after),
+            ]
+        }));
+        let mut widths = [0; 4];
+        for line in &cells {
+            for (width, cell) in widths.iter_mut().zip(line) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        for line in &cells {
+            let mut text = String::new();
+            for (width, cell) in widths.iter().zip(line) {
+                text += &format!("{:<w$}  ", cell, w = width);
+            }
+            writeln!(f, "{}", text.trim_end())?;
+        }
+        match &self.result {
+            Ok(value) => write!(f, "result {}", value),
+            Err(e) => write!(f, "error {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_an_aligned_table() {
+        assert_eq!(
+            trace_postfix("2 3 +").to_string(),
+            "#  token  before  after\n\
+             0  2              2\n\
+             1  3      2       2 3\n\
+             2  +      2 3     5\n\
+             result 5"
+        );
+        assert_eq!(
+            trace_postfix("10 2 over 0 / -").to_string(),
+            "#  token  before   after\n\
+             0  10              10\n\
+             1  2      10       10 2\n\
+             2  over   10 2     10 2 10\n\
+             3  0      10 2 10  10 2 10 0\n\
+             error token 4 (byte 12): division by zero"
+        );
+    }
+
+    #[test]
+    fn hooks_see_every_step() {
+        let registry = Registry::standard();
+        let formula = Formula::parse("a 1 + =b b b *", &registry).unwrap();
+        let mut env = HashMap::from([("a".to_string(), 4)]);
+        let mut depths = Vec::new();
+        let result = formula.eval_traced(&mut env, |step| {
+            assert_eq!(step.before.len(), depths.last().copied().unwrap_or(0));
+            depths.push(step.after.len());
+        });
+        assert_eq!(result, Ok(25));
+        assert_eq!(depths, [1, 2, 1, 0, 1, 2, 1]);
+
+        let trace = trace_postfix("1 2 swap drop");
+        let swap = &trace.rows[2];
+        assert_eq!((swap.text.as_str(), swap.offset), ("swap", 4));
+        assert_eq!(
+            (&swap.before[..], &swap.after[..]),
+            (&[1, 2][..], &[2, 1][..])
+        );
+        assert_eq!(trace.result, Ok(2));
+        assert!(trace_postfix("1 &").rows.is_empty());
+    }
+}
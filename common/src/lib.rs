@@ -0,0 +1,4 @@
+//! Code shared between submissions.
+
+pub mod scanner;
+pub mod solver;
@@ -0,0 +1,215 @@
+//! Token-at-a-time input reader, the replacement for the usual
+//! `read_line` + `split_whitespace` + `parse().unwrap()` boilerplate.
+//!
+//! The scanner keeps a single line buffer that is reused for every line, so
+//! reading numbers never allocates once the buffer has grown to the longest
+//! input line.
+
+use std::any;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead};
+use std::str::{self, FromStr};
+
+/// What went wrong while reading a token.
+#[derive(Debug)]
+pub enum ScanErrorKind {
+    /// The input ended before the requested token.
+    Eof,
+    /// The underlying reader failed.
+    Io(io::Error),
+    /// The token is not valid UTF-8.
+    Utf8,
+    /// The token does not parse as the requested type.
+    Parse { token: String, expected: &'static str },
+}
+
+/// A failed read, located at the offending token (1-based line and byte column).
+#[derive(Debug)]
+pub struct ScanError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ScanErrorKind,
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: ", self.line, self.column)?;
+        match &self.kind {
+            ScanErrorKind::Eof => f.write_str("unexpected end of input"),
+            ScanErrorKind::Io(e) => write!(f, "read failed: {}", e),
+            ScanErrorKind::Utf8 => f.write_str("token is not valid UTF-8"),
+            ScanErrorKind::Parse { token, expected } => {
+                write!(f, "expected {}, found `{}`", expected, token)
+            }
+        }
+    }
+}
+
+impl Error for ScanError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            ScanErrorKind::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Reads whitespace separated tokens from `R`, one line at a time.
+pub struct Scanner<R> {
+    reader: R,
+    buf: Vec<u8>,
+    pos: usize,
+    line: usize,
+}
+
+impl<R: BufRead> Scanner<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: Vec::new(),
+            pos: 0,
+            line: 0,
+        }
+    }
+
+    /// Parses the next whitespace separated token.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next<T: FromStr>(&mut self) -> Result<T, ScanError> {
+        let (start, end) = self.token()?;
+        let column = start + 1;
+        let text = str::from_utf8(&self.buf[start..end]).map_err(|_| ScanError {
+            line: self.line,
+            column,
+            kind: ScanErrorKind::Utf8,
+        })?;
+        text.parse().map_err(|_| ScanError {
+            line: self.line,
+            column,
+            kind: ScanErrorKind::Parse {
+                token: text.to_owned(),
+                expected: any::type_name::<T>(),
+            },
+        })
+    }
+
+    /// Parses the next `n` tokens.
+    pub fn vec<T: FromStr>(&mut self, n: usize) -> Result<Vec<T>, ScanError> {
+        (0..n).map(|_| self.next()).collect()
+    }
+
+    /// Next whitespace separated token, as raw bytes borrowed from the buffer.
+    pub fn bytes(&mut self) -> Result<&[u8], ScanError> {
+        let (start, end) = self.token()?;
+        Ok(&self.buf[start..end])
+    }
+
+    /// Rest of the current line without its terminator, or the whole next
+    /// line when nothing but the terminator is left on the current one.
+    pub fn line(&mut self) -> Result<&str, ScanError> {
+        if trim_newline(&self.buf[self.pos..]).is_empty() && !self.fill()? {
+            return Err(self.error(ScanErrorKind::Eof));
+        }
+        let start = self.pos;
+        let rest = trim_newline(&self.buf[start..]);
+        self.pos = self.buf.len();
+        str::from_utf8(rest).map_err(|_| ScanError {
+            line: self.line,
+            column: start + 1,
+            kind: ScanErrorKind::Utf8,
+        })
+    }
+
+    /// Bounds of the next token in `buf`, refilling it as many times as needed.
+    fn token(&mut self) -> Result<(usize, usize), ScanError> {
+        loop {
+            while self.pos < self.buf.len() && self.buf[self.pos].is_ascii_whitespace() {
+                self.pos += 1;
+            }
+            if self.pos < self.buf.len() {
+                break;
+            }
+            if !self.fill()? {
+                return Err(self.error(ScanErrorKind::Eof));
+            }
+        }
+        let start = self.pos;
+        while self.pos < self.buf.len() && !self.buf[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+        Ok((start, self.pos))
+    }
+
+    /// Loads the next line into `buf`, returns false at end of input.
+    fn fill(&mut self) -> Result<bool, ScanError> {
+        self.buf.clear();
+        self.pos = 0;
+        match self.reader.read_until(b'\n', &mut self.buf) {
+            Ok(0) => Ok(false),
+            Ok(_) => {
+                self.line += 1;
+                Ok(true)
+            }
+            Err(e) => Err(self.error(ScanErrorKind::Io(e))),
+        }
+    }
+
+    fn error(&self, kind: ScanErrorKind) -> ScanError {
+        ScanError {
+            line: self.line.max(1),
+            column: self.pos + 1,
+            kind,
+        }
+    }
+}
+
+fn trim_newline(mut s: &[u8]) -> &[u8] {
+    if let [rest @ .., b'\n'] = s {
+        s = rest;
+    }
+    if let [rest @ .., b'\r'] = s {
+        s = rest;
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_across_lines() {
+        let mut scan = Scanner::new(&b"3 10\n  1 -2\n\n3\n"[..]);
+        assert_eq!(scan.next::<usize>().unwrap(), 3);
+        assert_eq!(scan.next::<i64>().unwrap(), 10);
+        assert_eq!(scan.vec::<i32>(3).unwrap(), vec![1, -2, 3]);
+        assert!(matches!(scan.next::<i32>().unwrap_err().kind, ScanErrorKind::Eof));
+    }
+
+    #[test]
+    fn bytes_and_lines() {
+        let mut scan = Scanner::new(&b"2\nAABAB\r\nhello world\n"[..]);
+        assert_eq!(scan.next::<u8>().unwrap(), 2);
+        assert_eq!(scan.bytes().unwrap(), b"AABAB");
+        assert_eq!(scan.line().unwrap(), "hello world");
+        assert!(matches!(scan.line().unwrap_err().kind, ScanErrorKind::Eof));
+    }
+
+    #[test]
+    fn line_keeps_the_rest_of_a_partially_read_line() {
+        let mut scan = Scanner::new(&b"1 two three\n\nlast"[..]);
+        assert_eq!(scan.next::<u8>().unwrap(), 1);
+        assert_eq!(scan.line().unwrap(), " two three");
+        assert_eq!(scan.line().unwrap(), "");
+        assert_eq!(scan.line().unwrap(), "last");
+    }
+
+    #[test]
+    fn parse_error_points_at_token() {
+        let mut scan = Scanner::new(&b"1 2\n3 x4\n"[..]);
+        assert_eq!(scan.vec::<i64>(3).unwrap(), vec![1, 2, 3]);
+        let err = scan.next::<i64>().unwrap_err();
+        assert_eq!((err.line, err.column), (2, 3));
+        assert_eq!(err.to_string(), "2:3: expected i64, found `x4`");
+    }
+}
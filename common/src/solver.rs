@@ -0,0 +1,97 @@
+//! Solutions read from any `BufRead` and write to any `Write`, so the same
+//! code runs on stdin/stdout when submitted and on byte slices in tests.
+
+use crate::scanner::ScanError;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, BufWriter, Write};
+
+/// Why a solver gave up on its input.
+#[derive(Debug)]
+pub enum SolveError {
+    /// Malformed or truncated input.
+    Scan(ScanError),
+    /// Reading or writing the streams failed.
+    Io(io::Error),
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolveError::Scan(e) => write!(f, "bad input: {}", e),
+            SolveError::Io(e) => write!(f, "io error: {}", e),
+        }
+    }
+}
+
+impl Error for SolveError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SolveError::Scan(e) => Some(e),
+            SolveError::Io(e) => Some(e),
+        }
+    }
+}
+
+impl From<ScanError> for SolveError {
+    fn from(e: ScanError) -> Self {
+        SolveError::Scan(e)
+    }
+}
+
+impl From<io::Error> for SolveError {
+    fn from(e: io::Error) -> Self {
+        SolveError::Io(e)
+    }
+}
+
+pub trait Solver {
+    /// Reads one whole test file from `input` and writes the answer to `output`.
+    fn solve<R: BufRead, W: Write>(&self, input: R, output: W) -> Result<(), SolveError>;
+
+    /// Solves stdin into a buffered stdout, this is what `main` calls.
+    fn run_stdio(&self) -> Result<(), SolveError> {
+        let stdin = io::stdin();
+        let stdout = io::stdout();
+        let mut output = BufWriter::new(stdout.lock());
+        self.solve(stdin.lock(), &mut output)?;
+        output.flush()?;
+        Ok(())
+    }
+
+    /// Solves an in-memory input and returns the produced bytes.
+    fn solve_bytes(&self, input: &[u8]) -> Result<Vec<u8>, SolveError> {
+        let mut output = Vec::new();
+        self.solve(input, &mut output)?;
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Scanner;
+
+    struct Sum;
+
+    impl Solver for Sum {
+        fn solve<R: BufRead, W: Write>(&self, input: R, mut output: W) -> Result<(), SolveError> {
+            let mut scan = Scanner::new(input);
+            let n = scan.next()?;
+            let total: i64 = scan.vec::<i64>(n)?.iter().sum();
+            writeln!(output, "{}", total)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn solve_bytes() {
+        assert_eq!(Sum.solve_bytes(b"3\n1 2 3\n").unwrap(), b"6\n");
+    }
+
+    #[test]
+    fn scan_errors_are_forwarded() {
+        let err = Sum.solve_bytes(b"3\n1 2\n").unwrap_err();
+        assert!(matches!(err, SolveError::Scan(_)));
+    }
+}
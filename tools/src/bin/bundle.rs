@@ -0,0 +1,62 @@
+//! Bundles a solution and the shared modules it uses into one source file.
+//!
+//! Usage: `bundle SOLUTION.rs [--lib DIR] [-o OUT.rs]`
+//!
+//! `--lib` is the `src` directory of the shared crate, `common/src` by
+//! default. The bundle is written to stdout unless `-o` is given.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use tools::bundle;
+
+fn main() -> ExitCode {
+    let mut solution = None;
+    let mut lib_dir = PathBuf::from("common/src");
+    let mut output = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--lib" | "-o" => {
+                let Some(value) = args.next() else {
+                    return usage(&format!("{} needs a path", arg));
+                };
+                if arg == "--lib" {
+                    lib_dir = PathBuf::from(value);
+                } else {
+                    output = Some(PathBuf::from(value));
+                }
+            }
+            _ if solution.is_none() => solution = Some(PathBuf::from(arg)),
+            _ => return usage(&format!("unexpected argument `{}`", arg)),
+        }
+    }
+    let Some(solution) = solution else {
+        return usage("missing solution file");
+    };
+
+    let bundled = match bundle::bundle(&solution, &lib_dir) {
+        Ok(bundled) => bundled,
+        Err(e) => {
+            eprintln!("bundle: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    match output {
+        Some(path) => {
+            if let Err(e) = fs::write(&path, bundled) {
+                eprintln!("bundle: {}: {}", path.display(), e);
+                return ExitCode::FAILURE;
+            }
+        }
+        None => print!("{}", bundled),
+    }
+    ExitCode::SUCCESS
+}
+
+fn usage(message: &str) -> ExitCode {
+    eprintln!("bundle: {}", message);
+    eprintln!("usage: bundle SOLUTION.rs [--lib DIR] [-o OUT.rs]");
+    ExitCode::from(2)
+}
@@ -0,0 +1,79 @@
+//! Lists the problems solved in the repository, from their header comments.
+//!
+//! Usage: `catalog [--tag TAG] [--platform codeforces|codewars] [DIR]...`
+//!
+//! Directories default to `codeforces/` and `codewars/`. Files whose header
+//! link is missing or malformed are reported on stderr and make the exit
+//! status non-zero.
+
+use std::env;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use tools::catalog::{self, Platform};
+
+fn main() -> ExitCode {
+    let mut tag = None;
+    let mut platform = None;
+    let mut dirs = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--tag" => match args.next() {
+                Some(value) => tag = Some(value),
+                None => return usage("--tag needs a value"),
+            },
+            "--platform" => match args.next().as_deref().map(Platform::from_name) {
+                Some(Some(value)) => platform = Some(value),
+                Some(None) => return usage("--platform is codeforces or codewars"),
+                None => return usage("--platform needs a value"),
+            },
+            _ => dirs.push(PathBuf::from(arg)),
+        }
+    }
+    if dirs.is_empty() {
+        dirs = vec![PathBuf::from("codeforces"), PathBuf::from("codewars")];
+    }
+
+    let mut flagged = 0;
+    for dir in &dirs {
+        let entries = match catalog::scan(dir) {
+            Ok(entries) => entries,
+            Err(e) => return usage(&format!("{}: {}", dir.display(), e)),
+        };
+        for entry in entries {
+            let header = match entry.header {
+                Ok(header) => header,
+                Err(e) => {
+                    eprintln!("{}: {}", entry.source.display(), e);
+                    flagged += 1;
+                    continue;
+                }
+            };
+            if platform.is_some_and(|p| p != header.problem.platform())
+                || tag.as_ref().is_some_and(|t| !header.tags.contains(t))
+            {
+                continue;
+            }
+            println!(
+                "{:<20} {:<10} {:<26} {:<16} {}",
+                entry.source.display(),
+                header.problem.platform().name(),
+                header.problem.to_string(),
+                header.tags.join(","),
+                header.url
+            );
+        }
+    }
+    if flagged > 0 {
+        eprintln!("{} file(s) without a valid header", flagged);
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn usage(message: &str) -> ExitCode {
+    eprintln!("catalog: {}", message);
+    eprintln!("usage: catalog [--tag TAG] [--platform codeforces|codewars] [DIR]...");
+    ExitCode::from(2)
+}
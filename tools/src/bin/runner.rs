@@ -0,0 +1,112 @@
+//! Runs every solution against its checked-in fixtures.
+//!
+//! Usage: `runner [--bin-dir DIR] [--time SECS] [--wall SECS] [--memory MB]
+//! [SOLUTION.rs | DIR]...`
+//!
+//! Solutions are taken from `codeforces/` when no path is given. Binaries are
+//! looked up in `--bin-dir`, by default the directory of the runner itself,
+//! so `cargo build --workspace` has to run first. The limit flags override
+//! the `limits` file of every problem.
+
+use std::env;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use tools::fixture::{self, Problem};
+use tools::limits::Limits;
+use tools::runner;
+
+fn main() -> ExitCode {
+    let mut bin_dir = None;
+    let mut overrides = String::new();
+    let mut paths = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bin-dir" => match args.next() {
+                Some(dir) => bin_dir = Some(PathBuf::from(dir)),
+                None => return usage("--bin-dir needs a directory"),
+            },
+            "--time" | "--wall" | "--memory" => match args.next() {
+                Some(value) => overrides += &format!("{} {}\n", &arg[2..], value),
+                None => return usage(&format!("{} needs a value", arg)),
+            },
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+    if paths.is_empty() {
+        paths.push(PathBuf::from("codeforces"));
+    }
+    let bin_dir = match bin_dir.map_or_else(current_exe_dir, Ok) {
+        Ok(dir) => dir,
+        Err(e) => return usage(&format!("cannot locate binaries: {}", e)),
+    };
+
+    if let Err(e) = Limits::default().with(&overrides) {
+        return usage(&e);
+    }
+    let problems = match load(&paths) {
+        Ok(problems) => problems,
+        Err(e) => return usage(&e.to_string()),
+    };
+
+    let (mut total, mut failed) = (0, 0);
+    for problem in &problems {
+        println!(
+            "{} {}",
+            problem.source.display(),
+            problem.url().unwrap_or_default()
+        );
+        let exe = bin_dir
+            .join(problem.binary_name())
+            .with_extension(env::consts::EXE_EXTENSION);
+        let limits = problem
+            .limits
+            .with(&overrides)
+            .expect("overrides were validated");
+        for case in &problem.fixtures {
+            total += 1;
+            let report = runner::run(&exe, case, &problem.checker, &limits);
+            let ok = matches!(&report, Ok(r) if r.verdict.is_ok());
+            match report {
+                Ok(report) => println!("  {} {}", case.name, report),
+                Err(e) => println!("  {} cannot run {}: {}", case.name, exe.display(), e),
+            }
+            if !ok {
+                failed += 1;
+            }
+        }
+    }
+
+    println!("{} of {} cases passed", total - failed, total);
+    if failed == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn load(paths: &[PathBuf]) -> io::Result<Vec<Problem>> {
+    let mut problems = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            problems.extend(fixture::discover(path)?);
+        } else {
+            problems.push(Problem::load(path)?);
+        }
+    }
+    Ok(problems)
+}
+
+fn current_exe_dir() -> io::Result<PathBuf> {
+    let exe = env::current_exe()?;
+    Ok(exe.parent().map(Path::to_path_buf).unwrap_or_default())
+}
+
+fn usage(message: &str) -> ExitCode {
+    eprintln!("runner: {}", message);
+    eprintln!(
+        "usage: runner [--bin-dir DIR] [--time SECS] [--wall SECS] [--memory MB] [SOLUTION.rs | DIR]..."
+    );
+    ExitCode::from(2)
+}
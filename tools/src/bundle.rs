@@ -0,0 +1,466 @@
+//! Turns a solution using the shared `common` crate into the single
+//! self-contained source file the judge accepts.
+//!
+//! The `common::` paths of the solution name the modules it needs; those are
+//! read from the crate sources, followed through their own `crate::` paths,
+//! and appended to the solution inside a `mod common { .. }` block. Test-only
+//! items (`#[test]` and `#[cfg(test)]`) are dropped on the way.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub enum BundleError {
+    Io(PathBuf, io::Error),
+    /// Crates the bundle still refers to that are neither std nor bundled.
+    ExternalDependencies(Vec<String>),
+}
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BundleError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            BundleError::ExternalDependencies(crates) => {
+                write!(f, "bundle still depends on: {}", crates.join(", "))
+            }
+        }
+    }
+}
+
+/// Path roots a submission may use without shipping anything.
+const BUILTIN_ROOTS: &[&str] = &["std", "core", "alloc", "crate", "self", "super"];
+
+/// Bundles `solution` with the modules of the shared crate whose `src`
+/// directory is `lib_dir`, keeping only the modules it transitively uses.
+pub fn bundle(solution: &Path, lib_dir: &Path) -> Result<String, BundleError> {
+    let source = strip_test_items(&read(solution)?);
+
+    let mut pending: Vec<String> = references(&source, "common").into_iter().collect();
+    let mut modules = BTreeSet::new();
+    let mut bodies = Vec::new();
+    while let Some(name) = pending.pop() {
+        if !modules.insert(name.clone()) {
+            continue;
+        }
+        let body = strip_test_items(&read(&module_path(lib_dir, &name))?);
+        pending.extend(references(&body, "crate"));
+        bodies.push((name, rewrite_crate_paths(&body)));
+    }
+    bodies.sort();
+
+    let mut out = source.trim_end().to_owned();
+    out.push('\n');
+    if !bodies.is_empty() {
+        out.push_str("\n// ---- bundled from the common crate ----\n");
+        out.push_str("#[allow(dead_code)]\nmod common {\n");
+        for (name, body) in &bodies {
+            out.push_str(&format!("pub mod {} {{\n{}\n}}\n", name, body.trim()));
+        }
+        out.push_str("}\n");
+    }
+
+    let external = external_dependencies(&out);
+    if external.is_empty() {
+        Ok(out)
+    } else {
+        Err(BundleError::ExternalDependencies(external))
+    }
+}
+
+fn read(path: &Path) -> Result<String, BundleError> {
+    fs::read_to_string(path).map_err(|e| BundleError::Io(path.to_path_buf(), e))
+}
+
+fn module_path(lib_dir: &Path, name: &str) -> PathBuf {
+    let file = lib_dir.join(format!("{}.rs", name));
+    if file.is_file() {
+        file
+    } else {
+        lib_dir.join(name).join("mod.rs")
+    }
+}
+
+/// Crates used by `source` through `use` or `extern crate` that are not std
+/// and not a module declared in `source` itself.
+pub fn external_dependencies(source: &str) -> Vec<String> {
+    let code = mask(source);
+    let local: BTreeSet<String> = words_after(&code, "mod").into_iter().collect();
+    let mut external = BTreeSet::new();
+    for at in word_positions(&code, "use") {
+        let rest = code[at + "use".len()..].trim_start();
+        let rest = rest.strip_prefix("::").unwrap_or(rest).trim_start();
+        let roots = match rest.strip_prefix('{') {
+            Some(group) => group_heads(group),
+            None => leading_ident(rest).into_iter().collect(),
+        };
+        for root in roots {
+            if !BUILTIN_ROOTS.contains(&root.as_str()) && !local.contains(&root) {
+                external.insert(root);
+            }
+        }
+    }
+    for at in word_positions(&code, "extern") {
+        if let Some(rest) = code[at + "extern".len()..]
+            .trim_start()
+            .strip_prefix("crate ")
+        {
+            external.extend(leading_ident(rest.trim_start()));
+        }
+    }
+    external.into_iter().collect()
+}
+
+/// Top-level names reached through `root::`, a `root::{a, b::c}` group
+/// giving `a` and `b`.
+fn references(source: &str, root: &str) -> BTreeSet<String> {
+    let code = mask(source);
+    let mut names = BTreeSet::new();
+    for at in word_positions(&code, root) {
+        let Some(rest) = code[at + root.len()..].strip_prefix("::") else {
+            continue;
+        };
+        let rest = rest.trim_start();
+        match rest.strip_prefix('{') {
+            Some(group) => names.extend(group_heads(group)),
+            None => names.extend(leading_ident(rest)),
+        }
+    }
+    names.remove("self");
+    names
+}
+
+/// Prefixes every `crate::` path with `crate::common::`, the bundled module
+/// taking the place of the crate root.
+fn rewrite_crate_paths(source: &str) -> String {
+    let code = mask(source);
+    let mut out = String::with_capacity(source.len());
+    let mut last = 0;
+    for at in word_positions(&code, "crate") {
+        let end = at + "crate".len();
+        if code[end..].starts_with("::") && !code[..at].ends_with('$') {
+            out.push_str(&source[last..end + 2]);
+            out.push_str("common::");
+            last = end + 2;
+        }
+    }
+    out.push_str(&source[last..]);
+    out
+}
+
+/// Removes every item annotated `#[test]` or `#[cfg(test)]`, along with the
+/// attribute lines right above it.
+pub fn strip_test_items(source: &str) -> String {
+    let code = mask(source);
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for attribute in ["#[test]", "#[cfg(test)]"] {
+        for (at, _) in code.match_indices(attribute) {
+            let mut start = code[..at].rfind('\n').map_or(0, |i| i + 1);
+            while start > 0 {
+                let previous = code[..start - 1].rfind('\n').map_or(0, |i| i + 1);
+                let line = code[previous..start - 1].trim();
+                if line.starts_with("#[") && line.ends_with(']') {
+                    start = previous;
+                } else {
+                    break;
+                }
+            }
+            let mut end = item_end(&code, at + attribute.len());
+            if code[end..].starts_with('\n') {
+                end += 1;
+            }
+            ranges.push((start, end));
+        }
+    }
+    ranges.sort();
+
+    let mut out = String::with_capacity(source.len());
+    let mut last = 0;
+    for (start, end) in ranges {
+        if start >= last {
+            out.push_str(&source[last..start]);
+            last = end;
+        } else if end > last {
+            last = end;
+        }
+    }
+    out.push_str(&source[last..]);
+    out
+}
+
+/// End of the item starting at `from` (after its first attribute): the `;`
+/// or the brace closing its body, whichever comes first at nesting level 0.
+fn item_end(code: &str, from: usize) -> usize {
+    let mut depth = 0usize;
+    for (i, c) in code[from..].char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            ';' if depth == 0 => return from + i + 1,
+            '{' if depth == 0 => return matching_brace(code, from + i),
+            _ => {}
+        }
+    }
+    code.len()
+}
+
+fn matching_brace(code: &str, open: usize) -> usize {
+    let mut depth = 0usize;
+    for (i, c) in code[open..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return open + i + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    code.len()
+}
+
+/// Leading identifier of every item of a `{..}` group, `group` starting
+/// right after the opening brace.
+fn group_heads(group: &str) -> Vec<String> {
+    let mut heads = Vec::new();
+    let mut depth = 0usize;
+    let mut item_start = 0;
+    for (i, c) in group.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => {
+                heads.extend(leading_ident(group[item_start..i].trim_start()));
+                break;
+            }
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                heads.extend(leading_ident(group[item_start..i].trim_start()));
+                item_start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    heads
+}
+
+fn words_after(code: &str, keyword: &str) -> Vec<String> {
+    word_positions(code, keyword)
+        .into_iter()
+        .filter_map(|at| leading_ident(code[at + keyword.len()..].trim_start()))
+        .collect()
+}
+
+/// Byte offsets of `word` where it is a whole identifier.
+fn word_positions(code: &str, word: &str) -> Vec<usize> {
+    code.match_indices(word)
+        .map(|(at, _)| at)
+        .filter(|&at| {
+            let before = code[..at].chars().next_back();
+            let after = code[at + word.len()..].chars().next();
+            !before.is_some_and(is_ident_char) && !after.is_some_and(is_ident_char)
+        })
+        .collect()
+}
+
+fn leading_ident(s: &str) -> Option<String> {
+    let ident: String = s.chars().take_while(|&c| is_ident_char(c)).collect();
+    if ident.is_empty() {
+        None
+    } else {
+        Some(ident)
+    }
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Copy of `source` where comments and the contents of string and char
+/// literals are blanked out, byte offsets being preserved, so that code can
+/// be searched textually.
+fn mask(source: &str) -> String {
+    let chars: Vec<(usize, char)> = source.char_indices().collect();
+    let mut out = String::with_capacity(source.len());
+    let blank = |out: &mut String, c: char| {
+        if c == '\n' {
+            out.push('\n');
+        } else {
+            out.extend(std::iter::repeat_n(' ', c.len_utf8()));
+        }
+    };
+    let at = |i: usize| chars.get(i).map(|&(_, c)| c);
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i].1;
+        if c == '/' && at(i + 1) == Some('/') {
+            while i < chars.len() && chars[i].1 != '\n' {
+                blank(&mut out, chars[i].1);
+                i += 1;
+            }
+        } else if c == '/' && at(i + 1) == Some('*') {
+            let mut depth = 0;
+            while i < chars.len() {
+                if chars[i].1 == '/' && at(i + 1) == Some('*') {
+                    depth += 1;
+                    out.push_str("  ");
+                    i += 2;
+                } else if chars[i].1 == '*' && at(i + 1) == Some('/') {
+                    depth -= 1;
+                    out.push_str("  ");
+                    i += 2;
+                    if depth == 0 {
+                        break;
+                    }
+                } else {
+                    blank(&mut out, chars[i].1);
+                    i += 1;
+                }
+            }
+        } else if c == 'r'
+            && matches!(at(i + 1), Some('"') | Some('#'))
+            && (i == 0 || !is_ident_char(chars[i - 1].1) || chars[i - 1].1 == 'b')
+        {
+            let mut hashes = 0;
+            let mut j = i + 1;
+            while at(j) == Some('#') {
+                hashes += 1;
+                j += 1;
+            }
+            if at(j) != Some('"') {
+                out.push(c);
+                i += 1;
+                continue;
+            }
+            out.extend(chars[i..=j].iter().map(|&(_, c)| c));
+            i = j + 1;
+            while i < chars.len() {
+                if chars[i].1 == '"' && (1..=hashes).all(|h| at(i + h) == Some('#')) {
+                    for _ in 0..=hashes {
+                        out.push(chars[i].1);
+                        i += 1;
+                    }
+                    break;
+                }
+                blank(&mut out, chars[i].1);
+                i += 1;
+            }
+        } else if c == '"' {
+            out.push('"');
+            i += 1;
+            while i < chars.len() && chars[i].1 != '"' {
+                if chars[i].1 == '\\' {
+                    blank(&mut out, '\\');
+                    i += 1;
+                }
+                if i < chars.len() {
+                    blank(&mut out, chars[i].1);
+                    i += 1;
+                }
+            }
+            if i < chars.len() {
+                out.push('"');
+                i += 1;
+            }
+        } else if c == '\'' && (at(i + 1) == Some('\\') || at(i + 2) == Some('\'')) {
+            out.push('\'');
+            i += 1;
+            if at(i) == Some('\\') {
+                blank(&mut out, '\\');
+                i += 1;
+            }
+            while i < chars.len() && chars[i].1 != '\'' {
+                blank(&mut out, chars[i].1);
+                i += 1;
+            }
+            if i < chars.len() {
+                out.push('\'');
+                i += 1;
+            }
+        } else {
+            out.push(c);
+            i += 1;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    fn workspace() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("..")
+    }
+
+    #[test]
+    fn mask_blanks_comments_and_literals() {
+        let source = "let s = \"use x::y; // \\\"\"; // use z\nlet c = '{'; fn f<'a>() {} /* } */";
+        let masked = mask(source);
+        assert_eq!(masked.len(), source.len());
+        assert_eq!(
+            masked,
+            "let s = \"               \";         \nlet c = ' '; fn f<'a>() {}        "
+        );
+        assert_eq!(mask("r#\"a \"b\"# x"), "r#\"    \"# x");
+    }
+
+    #[test]
+    fn test_items_are_stripped() {
+        let source = "fn main() {}\n\n#[cfg(test)]\n#[path = \"1.rs\"]\nmod brute;\n\n#[test]\nfn t() {\n    let s = \"}\";\n}\n\n#[cfg(test)]\nmod tests {\n    #[test]\n    fn u() {}\n}\nfn after() {}\n";
+        assert_eq!(
+            strip_test_items(source),
+            "fn main() {}\n\n\n\nfn after() {}\n"
+        );
+    }
+
+    #[test]
+    fn references_follow_paths_and_groups() {
+        let source = "use common::{scanner::Scanner, solver::{SolveError, Solver}};\nfn f() { common::bitset::x(); }\n// common::ignored\n";
+        let names: Vec<String> = references(source, "common").into_iter().collect();
+        assert_eq!(names, ["bitset", "scanner", "solver"]);
+    }
+
+    #[test]
+    fn crate_paths_point_into_the_bundled_module() {
+        assert_eq!(
+            rewrite_crate_paths("use crate::scanner::ScanError; // crate::x\n"),
+            "use crate::common::scanner::ScanError; // crate::x\n"
+        );
+    }
+
+    #[test]
+    fn detects_external_dependencies() {
+        let source =
+            "use std::io;\nuse ::regex::Regex;\nuse local::x;\nmod local {}\nextern crate rand;\n";
+        assert_eq!(external_dependencies(source), ["rand", "regex"]);
+    }
+
+    #[test]
+    fn bundled_solution_compiles_alone() {
+        let root = workspace();
+        let solution = root.join("codeforces/3.rs");
+        let bundled = bundle(&solution, &root.join("common/src")).unwrap();
+        assert!(bundled.contains("pub mod scanner {"));
+        assert!(bundled.contains("pub mod solver {"));
+        assert!(!bundled.contains("mod brute"));
+        assert!(!bundled.contains("tools::"));
+
+        let dir = std::env::temp_dir().join(format!("bundle-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("main.rs");
+        fs::write(&file, &bundled).unwrap();
+        let status = Command::new("rustc")
+            .args(["--edition", "2021", "--crate-type", "bin", "-o"])
+            .arg(dir.join("main"))
+            .arg(&file)
+            .status()
+            .unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(status.success());
+    }
+}
@@ -0,0 +1,322 @@
+//! Problem metadata read from the header comment of each submission.
+//!
+//! A header is the comment block at the top of a file, imports and
+//! `#include`s being allowed before it. Its first link names the problem and
+//! the comment line right below the link, when it is a comma separated list
+//! such as `// greedy, sorting`, holds the tags.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Codeforces,
+    Codewars,
+}
+
+impl Platform {
+    pub fn name(self) -> &'static str {
+        match self {
+            Platform::Codeforces => "codeforces",
+            Platform::Codewars => "codewars",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "codeforces" => Some(Platform::Codeforces),
+            "codewars" => Some(Platform::Codewars),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProblemId {
+    /// Contest id and problem index, `279` and `B` for 279B.
+    Codeforces {
+        contest: u32,
+        index: String,
+    },
+    Codewars {
+        kata: String,
+    },
+}
+
+impl ProblemId {
+    pub fn platform(&self) -> Platform {
+        match self {
+            ProblemId::Codeforces { .. } => Platform::Codeforces,
+            ProblemId::Codewars { .. } => Platform::Codewars,
+        }
+    }
+
+    /// Recognizes `codeforces.com/contest/C/problem/I`,
+    /// `codeforces.com/problemset/problem/C/I` and `codewars.com/kata/ID`
+    /// links, with or without `www.` and trailing path segments.
+    pub fn from_url(url: &str) -> Result<Self, String> {
+        let rest = url
+            .strip_prefix("https://")
+            .or_else(|| url.strip_prefix("http://"))
+            .ok_or("not an http link")?;
+        let rest = rest.strip_prefix("www.").unwrap_or(rest);
+        let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        match (host, segments.as_slice()) {
+            ("codeforces.com", ["contest", contest, "problem", index, ..])
+            | ("codeforces.com", ["problemset", "problem", contest, index, ..]) => {
+                let contest = contest
+                    .parse()
+                    .map_err(|_| format!("invalid contest id `{}`", contest))?;
+                if !index.chars().all(|c| c.is_ascii_alphanumeric()) {
+                    return Err(format!("invalid problem index `{}`", index));
+                }
+                Ok(ProblemId::Codeforces {
+                    contest,
+                    index: index.to_ascii_uppercase(),
+                })
+            }
+            ("codewars.com", ["kata", kata, ..]) => {
+                if !kata.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+                    return Err(format!("invalid kata id `{}`", kata));
+                }
+                Ok(ProblemId::Codewars {
+                    kata: kata.to_string(),
+                })
+            }
+            ("codeforces.com", _) | ("codewars.com", _) => Err("not a problem link".to_owned()),
+            _ => Err(format!("unknown site `{}`", host)),
+        }
+    }
+}
+
+impl fmt::Display for ProblemId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProblemId::Codeforces { contest, index } => write!(f, "{}{}", contest, index),
+            ProblemId::Codewars { kata } => f.write_str(kata),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    pub url: String,
+    pub problem: ProblemId,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HeaderError {
+    /// No link in the header comments.
+    Missing,
+    /// A link is there but does not point to a known problem.
+    Malformed { url: String, reason: String },
+}
+
+impl fmt::Display for HeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeaderError::Missing => f.write_str("missing header link"),
+            HeaderError::Malformed { url, reason } => {
+                write!(f, "malformed link {}: {}", url, reason)
+            }
+        }
+    }
+}
+
+/// Comment texts of the header, `//` markers stripped.
+fn header_comments(source: &str) -> Vec<&str> {
+    let mut comments = Vec::new();
+    for line in source.lines().map(str::trim) {
+        if let Some(comment) = line.strip_prefix("//") {
+            comments.push(comment.trim_start_matches(['/', '!']).trim());
+        } else if !(line.is_empty() || line.starts_with("use ") || line.starts_with("#include")) {
+            break;
+        }
+    }
+    comments
+}
+
+/// First link of the header comments.
+pub fn find_url(source: &str) -> Option<&str> {
+    header_comments(source).into_iter().find_map(url_in)
+}
+
+fn url_in(comment: &str) -> Option<&str> {
+    comment
+        .split_whitespace()
+        .find(|word| word.starts_with("http://") || word.starts_with("https://"))
+}
+
+pub fn parse_header(source: &str) -> Result<Header, HeaderError> {
+    let comments = header_comments(source);
+    let (at, url) = comments
+        .iter()
+        .enumerate()
+        .find_map(|(i, comment)| url_in(comment).map(|url| (i, url)))
+        .ok_or(HeaderError::Missing)?;
+    let problem = ProblemId::from_url(url).map_err(|reason| HeaderError::Malformed {
+        url: url.to_owned(),
+        reason,
+    })?;
+    let tags = comments
+        .get(at + 1)
+        .filter(|_| source_line_follows(source, url))
+        .and_then(|line| parse_tags(line))
+        .unwrap_or_default();
+    Ok(Header {
+        url: url.to_owned(),
+        problem,
+        tags,
+    })
+}
+
+/// Whether the line right after the one holding `url` is a comment.
+fn source_line_follows(source: &str, url: &str) -> bool {
+    let mut lines = source.lines().skip_while(|line| !line.contains(url));
+    lines.next();
+    lines
+        .next()
+        .is_some_and(|line| line.trim().starts_with("//"))
+}
+
+fn parse_tags(line: &str) -> Option<Vec<String>> {
+    let tags: Vec<String> = line.split(',').map(|tag| tag.trim().to_owned()).collect();
+    let valid = |tag: &String| {
+        !tag.is_empty()
+            && tag.split(' ').count() <= 3
+            && tag
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || " *-".contains(c))
+    };
+    if tags.iter().all(valid) {
+        Some(tags)
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub source: PathBuf,
+    pub header: Result<Header, HeaderError>,
+}
+
+/// Every submission in `dir`: files with a numeric stem, such as `4.rs` or
+/// `10.cpp`, in numeric order.
+pub fn scan(dir: &Path) -> io::Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let numbered = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .is_some_and(|stem| stem.parse::<u32>().is_ok());
+        if path.is_file() && numbered {
+            let header = parse_header(&fs::read_to_string(&path)?);
+            entries.push(Entry {
+                source: path,
+                header,
+            });
+        }
+    }
+    entries.sort_by_key(|entry| {
+        let stem = entry
+            .source
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy();
+        (
+            stem.parse::<u32>().unwrap_or(u32::MAX),
+            entry.source.clone(),
+        )
+    });
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_problem_links() {
+        assert_eq!(
+            ProblemId::from_url("https://codeforces.com/contest/1672/problem/B"),
+            Ok(ProblemId::Codeforces {
+                contest: 1672,
+                index: "B".to_owned()
+            })
+        );
+        assert_eq!(
+            ProblemId::from_url("https://codeforces.com/problemset/problem/16/E")
+                .unwrap()
+                .to_string(),
+            "16E"
+        );
+        assert_eq!(
+            ProblemId::from_url("https://www.codewars.com/kata/577e9095d648a15b800000d4/discuss"),
+            Ok(ProblemId::Codewars {
+                kata: "577e9095d648a15b800000d4".to_owned()
+            })
+        );
+        assert!(ProblemId::from_url("https://codeforces.com/blog/entry/1").is_err());
+        assert!(ProblemId::from_url("https://codeforces.com/contest/x/problem/A").is_err());
+        assert!(ProblemId::from_url("https://example.com/kata/1").is_err());
+    }
+
+    #[test]
+    fn tags_follow_the_link() {
+        let header = parse_header("// https://codeforces.com/problemset/problem/492/B\n// greedy, sorting\nuse std::io;\n").unwrap();
+        assert_eq!(header.tags, ["greedy", "sorting"]);
+        assert_eq!(header.problem.platform(), Platform::Codeforces);
+    }
+
+    #[test]
+    fn link_may_come_after_imports_and_plain_comments_are_not_tags() {
+        let header = parse_header("use std::collections::HashMap;\n\n// https://www.codewars.com/kata/58e24788e24ddee28e000053\n\n// either variable name or a value\nenum Operand {}\n").unwrap();
+        assert_eq!(header.problem.to_string(), "58e24788e24ddee28e000053");
+        assert!(header.tags.is_empty());
+
+        let header = parse_header(
+            "// Solution to https://codeforces.com/contest/1672/problem/B\n// not, Tags here\n",
+        )
+        .unwrap();
+        assert!(header.tags.is_empty());
+    }
+
+    #[test]
+    fn reports_missing_and_malformed_headers() {
+        assert_eq!(
+            parse_header(
+                "use std::io;\nfn main() {}\n// https://codeforces.com/contest/1/problem/A\n"
+            ),
+            Err(HeaderError::Missing)
+        );
+        assert!(matches!(
+            parse_header("// https://codeforces.com/contest/1\n"),
+            Err(HeaderError::Malformed { .. })
+        ));
+    }
+
+    #[test]
+    fn scans_the_repository() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+        let entries = scan(&root.join("codeforces")).unwrap();
+        assert!(entries.iter().all(|entry| entry.header.is_ok()));
+        let lanterns = entries.iter().find(|e| e.source.ends_with("6.rs")).unwrap();
+        assert_eq!(
+            lanterns.header.as_ref().unwrap().tags,
+            ["greedy", "sorting"]
+        );
+        assert!(entries.iter().any(|e| e.source.ends_with("12.cpp")));
+
+        let katas = scan(&root.join("codewars")).unwrap();
+        assert_eq!(katas.len(), 2);
+        assert!(katas
+            .iter()
+            .all(|e| e.header.as_ref().unwrap().problem.platform() == Platform::Codewars));
+    }
+}
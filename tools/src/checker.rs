@@ -0,0 +1,290 @@
+//! Output checkers, picked per problem by a `checker` file in its fixture
+//! directory.
+//!
+//! The file holds a single line: `exact`, `lines`, `float EPS` or
+//! `float ABS REL`. Problems without one are checked with `exact`.
+
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Checker {
+    /// Whitespace separated tokens must be identical.
+    #[default]
+    Exact,
+    /// Same lines in the same order, each compared token-wise so spacing
+    /// inside a line and trailing blank lines do not matter.
+    Lines,
+    /// Tokens that parse as numbers may differ by up to `abs` absolutely or
+    /// `rel` relatively to the expected value; other tokens must be identical.
+    Float { abs: f64, rel: f64 },
+}
+
+impl FromStr for Checker {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        let eps = |word: &str| {
+            word.parse::<f64>()
+                .map_err(|_| format!("invalid epsilon `{}`", word))
+        };
+        match words.as_slice() {
+            ["exact"] => Ok(Checker::Exact),
+            ["lines"] => Ok(Checker::Lines),
+            ["float"] => Ok(Checker::Float {
+                abs: 1e-6,
+                rel: 1e-6,
+            }),
+            ["float", eps_both] => {
+                let e = eps(eps_both)?;
+                Ok(Checker::Float { abs: e, rel: e })
+            }
+            ["float", abs, rel] => Ok(Checker::Float {
+                abs: eps(abs)?,
+                rel: eps(rel)?,
+            }),
+            _ => Err(format!("unknown checker `{}`", s.trim())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Position {
+    /// 1-based token index.
+    Token(usize),
+    /// 1-based line number.
+    Line(usize),
+}
+
+/// First place where the produced output disagrees with the answer,
+/// `None` standing for the end of the corresponding output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub position: Position,
+    pub expected: Option<String>,
+    pub found: Option<String>,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.position {
+            Position::Token(n) => write!(f, "token {}", n)?,
+            Position::Line(n) => write!(f, "line {}", n)?,
+        }
+        write!(
+            f,
+            ": expected {}, found {}",
+            quoted(&self.expected),
+            quoted(&self.found)
+        )
+    }
+}
+
+fn quoted(token: &Option<String>) -> String {
+    match token {
+        Some(token) => format!("`{}`", token),
+        None => "end of output".to_owned(),
+    }
+}
+
+/// Largest numeric error seen by the float checker.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Deviation {
+    /// 1-based token index.
+    pub token: usize,
+    pub absolute: f64,
+    pub relative: f64,
+}
+
+impl fmt::Display for Deviation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "max error {:.1e} at token {} (relative {:.1e})",
+            self.absolute, self.token, self.relative
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Check {
+    pub mismatch: Option<Mismatch>,
+    /// Only filled by [`Checker::Float`], and only when a numeric token was compared.
+    pub worst: Option<Deviation>,
+}
+
+impl Checker {
+    pub fn check(&self, expected: &str, found: &str) -> Check {
+        match *self {
+            Checker::Exact => Check {
+                mismatch: tokens(expected, found, |e, f| e == f),
+                worst: None,
+            },
+            Checker::Lines => Check {
+                mismatch: lines(expected, found),
+                worst: None,
+            },
+            Checker::Float { abs, rel } => {
+                let mut worst: Option<Deviation> = None;
+                let mut index = 0;
+                let mismatch = tokens(expected, found, |e, f| {
+                    index += 1;
+                    match (e.parse::<f64>(), f.parse::<f64>()) {
+                        (Ok(e), Ok(f)) if e.is_finite() && f.is_finite() => {
+                            let absolute = (e - f).abs();
+                            let relative = if e == 0. {
+                                absolute
+                            } else {
+                                absolute / e.abs()
+                            };
+                            if worst.is_none_or(|w| absolute > w.absolute) {
+                                worst = Some(Deviation {
+                                    token: index,
+                                    absolute,
+                                    relative,
+                                });
+                            }
+                            absolute <= abs || absolute <= rel * e.abs()
+                        }
+                        _ => e == f,
+                    }
+                });
+                Check { mismatch, worst }
+            }
+        }
+    }
+}
+
+fn tokens<F>(expected: &str, found: &str, mut same: F) -> Option<Mismatch>
+where
+    F: FnMut(&str, &str) -> bool,
+{
+    let mut expected = expected.split_whitespace();
+    let mut found = found.split_whitespace();
+    let mut index = 1;
+    loop {
+        match (expected.next(), found.next()) {
+            (None, None) => return None,
+            (Some(e), Some(f)) if same(e, f) => index += 1,
+            (e, f) => {
+                return Some(Mismatch {
+                    position: Position::Token(index),
+                    expected: e.map(str::to_owned),
+                    found: f.map(str::to_owned),
+                })
+            }
+        }
+    }
+}
+
+fn lines(expected: &str, found: &str) -> Option<Mismatch> {
+    let normalize = |s: &str| -> Vec<String> {
+        let mut lines: Vec<String> = s
+            .lines()
+            .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+            .collect();
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+        lines
+    };
+    let expected = normalize(expected);
+    let found = normalize(found);
+    (0..expected.len().max(found.len()))
+        .find(|&i| expected.get(i) != found.get(i))
+        .map(|i| Mismatch {
+            position: Position::Line(i + 1),
+            expected: expected.get(i).cloned(),
+            found: found.get(i).cloned(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_checker_files() {
+        assert_eq!("exact\n".parse::<Checker>(), Ok(Checker::Exact));
+        assert_eq!("lines".parse::<Checker>(), Ok(Checker::Lines));
+        assert_eq!(
+            "float 1e-9".parse::<Checker>(),
+            Ok(Checker::Float {
+                abs: 1e-9,
+                rel: 1e-9
+            })
+        );
+        assert_eq!(
+            "float 1e-6 0".parse::<Checker>(),
+            Ok(Checker::Float { abs: 1e-6, rel: 0. })
+        );
+        assert!("float x".parse::<Checker>().is_err());
+        assert!("diff".parse::<Checker>().is_err());
+    }
+
+    #[test]
+    fn exact_reports_the_first_differing_token() {
+        assert_eq!(
+            Checker::Exact.check("YES\nNO\n", "YES  NO"),
+            Check::default()
+        );
+        let check = Checker::Exact.check("69 96\n", "69 95\n");
+        assert_eq!(
+            check.mismatch.unwrap().to_string(),
+            "token 2: expected `96`, found `95`"
+        );
+        let check = Checker::Exact.check("1 2", "1");
+        assert_eq!(
+            check.mismatch.unwrap().to_string(),
+            "token 2: expected `2`, found end of output"
+        );
+    }
+
+    #[test]
+    fn lines_keep_line_structure() {
+        assert_eq!(
+            Checker::Lines.check("1  2\n3\n", "1 2 \n3\n\n").mismatch,
+            None
+        );
+        let check = Checker::Lines.check("1 2\n3\n", "1\n2 3\n");
+        assert_eq!(
+            check.mismatch.unwrap().to_string(),
+            "line 1: expected `1 2`, found `1`"
+        );
+    }
+
+    #[test]
+    fn float_accepts_within_epsilon_and_reports_worst_deviation() {
+        let checker = Checker::Float {
+            abs: 1e-6,
+            rel: 1e-6,
+        };
+        let check = checker.check("0.276667 0.226667 0.496667", "0.2766668 0.226667 0.4966665");
+        assert_eq!(check.mismatch, None);
+        let worst = check.worst.unwrap();
+        assert_eq!(worst.token, 3);
+        assert!((worst.absolute - 5e-7).abs() < 1e-12);
+
+        assert_eq!(checker.check("2.5000000000", "2.5").mismatch, None);
+        let check = checker.check("1.000000 0.000000", "1.000000 0.000100");
+        assert_eq!(check.mismatch.unwrap().position, Position::Token(2));
+        assert_eq!(check.worst.unwrap().token, 2);
+    }
+
+    #[test]
+    fn float_uses_relative_error_for_large_values() {
+        let checker = Checker::Float {
+            abs: 1e-9,
+            rel: 1e-9,
+        };
+        assert_eq!(
+            checker
+                .check("1000000000000", "1000000000000.0005")
+                .mismatch,
+            None
+        );
+        assert!(checker.check("1", "1.000001").mismatch.is_some());
+        assert!(checker.check("YES", "yes").mismatch.is_some());
+    }
+}
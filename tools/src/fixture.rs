@@ -0,0 +1,170 @@
+//! Sample tests stored next to the solutions.
+//!
+//! A solution `codeforces/4.rs` owns the directory `codeforces/4/`, where every
+//! `NN.in` file is a test input and the matching `NN.out` file its expected
+//! answer. Optional `checker` and `limits` files there select how answers are
+//! compared and how long and how much memory the solution gets, see
+//! [`crate::checker`] and [`crate::limits`].
+
+use crate::catalog;
+use crate::checker::Checker;
+use crate::limits::Limits;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fixture {
+    /// File stem shared by the input and the answer, e.g. `01`.
+    pub name: String,
+    pub input: PathBuf,
+    pub answer: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    /// The solution source file.
+    pub source: PathBuf,
+    pub fixtures: Vec<Fixture>,
+    pub checker: Checker,
+    pub limits: Limits,
+}
+
+impl Problem {
+    /// Loads the fixtures of a single solution file; an absent fixture
+    /// directory yields a problem without fixtures.
+    pub fn load(source: &Path) -> io::Result<Self> {
+        let dir = source.with_extension("");
+        let mut fixtures = Vec::new();
+        let mut checker = Checker::default();
+        let mut limits = Limits::default();
+        if dir.is_dir() {
+            if let Some(spec) = read_optional(&dir.join("checker"))? {
+                checker = spec.parse().map_err(|e| invalid(&dir.join("checker"), e))?;
+            }
+            if let Some(spec) = read_optional(&dir.join("limits"))? {
+                limits = spec.parse().map_err(|e| invalid(&dir.join("limits"), e))?;
+            }
+            for path in sorted_entries(&dir)? {
+                if path.extension().is_some_and(|ext| ext == "in") {
+                    let answer = path.with_extension("out");
+                    if answer.is_file() {
+                        fixtures.push(Fixture {
+                            name: stem(&path),
+                            input: path,
+                            answer,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(Problem {
+            source: source.to_path_buf(),
+            fixtures,
+            checker,
+            limits,
+        })
+    }
+
+    /// Name of the cargo binary built from this solution, `<dir>-<stem>`
+    /// (`codeforces/4.rs` is built as `codeforces-4`).
+    pub fn binary_name(&self) -> String {
+        let dir = self
+            .source
+            .parent()
+            .and_then(Path::file_name)
+            .map(|d| d.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        format!("{}-{}", dir, stem(&self.source))
+    }
+
+    /// Problem link from the header comments of the source, see
+    /// [`crate::catalog`].
+    pub fn url(&self) -> Option<String> {
+        let source = fs::read_to_string(&self.source).ok()?;
+        catalog::find_url(&source).map(str::to_owned)
+    }
+}
+
+/// Every `*.rs` solution in `dir` that has at least one fixture.
+pub fn discover(dir: &Path) -> io::Result<Vec<Problem>> {
+    let mut problems = Vec::new();
+    for path in sorted_entries(dir)? {
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "rs") {
+            let problem = Problem::load(&path)?;
+            if !problem.fixtures.is_empty() {
+                problems.push(problem);
+            }
+        }
+    }
+    Ok(problems)
+}
+
+fn read_optional(path: &Path) -> io::Result<Option<String>> {
+    if path.is_file() {
+        fs::read_to_string(path).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+fn invalid(path: &Path, message: String) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}: {}", path.display(), message),
+    )
+}
+
+/// Directory entries ordered numerically when the stems are numbers, so `2`
+/// comes before `10`.
+fn sorted_entries(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|path| {
+        let stem = stem(path);
+        (stem.parse::<u64>().unwrap_or(u64::MAX), stem)
+    });
+    Ok(entries)
+}
+
+fn stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workspace() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("..")
+    }
+
+    #[test]
+    fn discovers_checked_in_fixtures() {
+        let problems = discover(&workspace().join("codeforces")).unwrap();
+        let books = problems
+            .iter()
+            .find(|p| p.source.ends_with("codeforces/4.rs"))
+            .unwrap();
+        assert_eq!(books.binary_name(), "codeforces-4");
+        assert_eq!(
+            books.url().as_deref(),
+            Some("https://codeforces.com/contest/279/problem/B")
+        );
+        assert_eq!(books.fixtures[0].name, "01");
+        assert!(books.fixtures[0].answer.ends_with("codeforces/4/01.out"));
+        assert_eq!(books.checker, Checker::Exact);
+
+        let lanterns = Problem::load(&workspace().join("codeforces/6.rs")).unwrap();
+        assert_eq!(
+            lanterns.checker,
+            Checker::Float {
+                abs: 1e-9,
+                rel: 1e-9
+            }
+        );
+    }
+}
@@ -0,0 +1,488 @@
+//! Local judging of interactive problems.
+//!
+//! The solver and a [`Judge`] run on two threads connected by a pair of
+//! in-memory pipes. Both ends are buffered like stdin/stdout on a real
+//! judge, so a solver that forgets to flush its query hangs here too: when
+//! both sides are blocked reading an empty pipe the run ends as a deadlock
+//! instead of hanging the test. Every line crossing the pipes is kept in a
+//! transcript.
+
+use common::scanner::{ScanError, Scanner};
+use common::solver::Solver;
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+
+/// One end of the conversation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Solver,
+    Judge,
+}
+
+impl Side {
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    fn other(self) -> Side {
+        match self {
+            Side::Solver => Side::Judge,
+            Side::Judge => Side::Solver,
+        }
+    }
+}
+
+/// A line sent by `from` to the other side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    pub from: Side,
+    pub text: String,
+}
+
+/// The whole exchange, solver lines marked `>` and judge lines `<`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Transcript(pub Vec<Line>);
+
+impl fmt::Display for Transcript {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.0 {
+            let arrow = match line.from {
+                Side::Solver => '>',
+                Side::Judge => '<',
+            };
+            writeln!(f, "{} {}", arrow, line.text)?;
+        }
+        Ok(())
+    }
+}
+
+/// Why a judge rejected the solver.
+#[derive(Debug)]
+pub enum JudgeError {
+    WrongAnswer(String),
+    /// The solver asked more than this many queries.
+    QueryLimit(usize),
+    /// The solver sent something the judge could not read, or nothing.
+    Scan(ScanError),
+    /// Writing to the solver failed.
+    Io(io::Error),
+}
+
+impl JudgeError {
+    pub fn wrong(message: impl Into<String>) -> Self {
+        JudgeError::WrongAnswer(message.into())
+    }
+}
+
+impl From<ScanError> for JudgeError {
+    fn from(e: ScanError) -> Self {
+        JudgeError::Scan(e)
+    }
+}
+
+impl From<io::Error> for JudgeError {
+    fn from(e: io::Error) -> Self {
+        JudgeError::Io(e)
+    }
+}
+
+/// The judge's view of the solver: a scanner over what the solver wrote and
+/// a buffered writer to its input, which the judge has to flush after each
+/// reply.
+pub struct Session {
+    pub input: Scanner<BufReader<PipeReader>>,
+    pub output: BufWriter<PipeWriter>,
+    queries: usize,
+    query_limit: Option<usize>,
+}
+
+impl Session {
+    /// Counts one query, failing once the solver goes over the limit.
+    pub fn query(&mut self) -> Result<(), JudgeError> {
+        self.queries += 1;
+        match self.query_limit {
+            Some(limit) if self.queries > limit => Err(JudgeError::QueryLimit(limit)),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn queries(&self) -> usize {
+        self.queries
+    }
+}
+
+/// The checking side of an interactive problem. It holds the hidden test
+/// data and plays one game against the solver.
+pub trait Judge {
+    fn interact(&self, session: &mut Session) -> Result<(), JudgeError>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Accepted,
+    WrongAnswer(String),
+    QueryLimitExceeded(usize),
+    /// Both sides were waiting for the other one.
+    Deadlock,
+    /// The solver returned an error or panicked.
+    SolverError(String),
+    /// The judge itself failed, which is a bug in the judge.
+    JudgeError(String),
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Accepted => f.write_str("OK"),
+            Outcome::WrongAnswer(message) => write!(f, "WA {}", message),
+            Outcome::QueryLimitExceeded(limit) => write!(f, "QLE more than {} queries", limit),
+            Outcome::Deadlock => f.write_str("ILE both sides waiting, missing flush?"),
+            Outcome::SolverError(message) => write!(f, "RE {}", message),
+            Outcome::JudgeError(message) => write!(f, "judge failed: {}", message),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub outcome: Outcome,
+    pub queries: usize,
+    pub transcript: Transcript,
+}
+
+pub struct Interactor<J, S> {
+    pub judge: J,
+    pub solver: S,
+    pub query_limit: Option<usize>,
+}
+
+impl<J: Judge + Sync, S: Solver + Sync> Interactor<J, S> {
+    pub fn new(judge: J, solver: S) -> Self {
+        Interactor {
+            judge,
+            solver,
+            query_limit: None,
+        }
+    }
+
+    pub fn run(&self) -> Report {
+        let pipes = Arc::new(Pipes::default());
+        let (solver_in, solver_out) = pipes.ends(Side::Solver);
+        let (judge_in, judge_out) = pipes.ends(Side::Judge);
+        let mut session = Session {
+            input: Scanner::new(BufReader::new(judge_in)),
+            output: BufWriter::new(judge_out),
+            queries: 0,
+            query_limit: self.query_limit,
+        };
+
+        let (judged, queries, solved) = thread::scope(|scope| {
+            let solver = scope.spawn(|| {
+                self.solver
+                    .solve(BufReader::new(solver_in), BufWriter::new(solver_out))
+            });
+            let judged = self.judge.interact(&mut session);
+            let queries = session.queries;
+            // hang up, so that a solver still reading sees the end of input
+            drop(session);
+            (judged, queries, solver.join())
+        });
+
+        let state = pipes.lock();
+        let outcome = if state.deadlock {
+            Outcome::Deadlock
+        } else {
+            match (judged, solved) {
+                (Err(JudgeError::WrongAnswer(message)), _) => Outcome::WrongAnswer(message),
+                (Err(JudgeError::QueryLimit(limit)), _) => Outcome::QueryLimitExceeded(limit),
+                (_, Ok(Err(e))) => Outcome::SolverError(e.to_string()),
+                (_, Err(payload)) => Outcome::SolverError(format!(
+                    "panicked: {}",
+                    crate::stress::panic_message(&*payload)
+                )),
+                (Err(JudgeError::Scan(e)), _) => {
+                    Outcome::WrongAnswer(format!("unreadable output: {}", e))
+                }
+                (Err(JudgeError::Io(e)), _) => Outcome::JudgeError(e.to_string()),
+                (Ok(()), Ok(Ok(()))) => Outcome::Accepted,
+            }
+        };
+        let mut transcript = state.transcript.clone();
+        for side in [Side::Solver, Side::Judge] {
+            let partial = &state.partial[side.index()];
+            if !partial.is_empty() {
+                transcript.0.push(Line {
+                    from: side,
+                    text: String::from_utf8_lossy(partial).into_owned(),
+                });
+            }
+        }
+        Report {
+            outcome,
+            queries,
+            transcript,
+        }
+    }
+}
+
+/// Both directions of the conversation, indexed by the writing side.
+#[derive(Default)]
+struct Pipes {
+    state: Mutex<PipeState>,
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct PipeState {
+    buffers: [VecDeque<u8>; 2],
+    /// The writing end was dropped.
+    closed: [bool; 2],
+    /// The side is blocked reading; its incoming buffer is then empty.
+    waiting: [bool; 2],
+    deadlock: bool,
+    transcript: Transcript,
+    /// Bytes of the line each side is in the middle of writing.
+    partial: [Vec<u8>; 2],
+}
+
+impl Pipes {
+    fn lock(&self) -> MutexGuard<'_, PipeState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn ends(self: &Arc<Self>, side: Side) -> (PipeReader, PipeWriter) {
+        let reader = PipeReader {
+            pipes: Arc::clone(self),
+            side,
+        };
+        let writer = PipeWriter {
+            pipes: Arc::clone(self),
+            side,
+        };
+        (reader, writer)
+    }
+}
+
+/// What `side` reads: the bytes the other side wrote.
+pub struct PipeReader {
+    pipes: Arc<Pipes>,
+    side: Side,
+}
+
+impl Read for PipeReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let (me, peer) = (self.side.index(), self.side.other().index());
+        let mut state = self.pipes.lock();
+        loop {
+            if state.deadlock {
+                return Err(io::Error::other("deadlock"));
+            }
+            if !state.buffers[peer].is_empty() {
+                let n = buf.len().min(state.buffers[peer].len());
+                for (byte, value) in buf.iter_mut().zip(state.buffers[peer].drain(..n)) {
+                    *byte = value;
+                }
+                return Ok(n);
+            }
+            if state.closed[peer] {
+                return Ok(0);
+            }
+            if state.waiting[peer] {
+                state.deadlock = true;
+                self.pipes.changed.notify_all();
+                continue;
+            }
+            state.waiting[me] = true;
+            state = self
+                .pipes
+                .changed
+                .wait(state)
+                .unwrap_or_else(|e| e.into_inner());
+            state.waiting[me] = false;
+        }
+    }
+}
+
+/// What `side` writes, appended to the transcript line by line.
+pub struct PipeWriter {
+    pipes: Arc<Pipes>,
+    side: Side,
+}
+
+impl Write for PipeWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let (me, peer) = (self.side.index(), self.side.other().index());
+        let mut state = self.pipes.lock();
+        if state.deadlock {
+            return Err(io::Error::other("deadlock"));
+        }
+        state.buffers[me].extend(buf);
+        for &byte in buf {
+            if byte == b'\n' {
+                let text = String::from_utf8_lossy(&state.partial[me]).into_owned();
+                state.partial[me].clear();
+                state.transcript.0.push(Line {
+                    from: self.side,
+                    text,
+                });
+            } else {
+                state.partial[me].push(byte);
+            }
+        }
+        // the peer cannot be counted as waiting any more, even before it
+        // wakes up and clears the flag itself
+        state.waiting[peer] = false;
+        self.pipes.changed.notify_all();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for PipeWriter {
+    fn drop(&mut self) {
+        self.pipes.lock().closed[self.side.index()] = true;
+        self.pipes.changed.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::solver::SolveError;
+    use std::io::BufRead;
+
+    /// Guess the number in 1..=100, answering `<`, `>` or `=` to `? x` and
+    /// checking the final `! x`.
+    struct Guess(u32);
+
+    impl Judge for Guess {
+        fn interact(&self, session: &mut Session) -> Result<(), JudgeError> {
+            loop {
+                let kind: String = session.input.next()?;
+                let x: u32 = session.input.next()?;
+                match kind.as_str() {
+                    "?" => {
+                        session.query()?;
+                        let reply = match x.cmp(&self.0) {
+                            std::cmp::Ordering::Less => "<",
+                            std::cmp::Ordering::Greater => ">",
+                            std::cmp::Ordering::Equal => "=",
+                        };
+                        writeln!(session.output, "{}", reply)?;
+                        session.output.flush()?;
+                    }
+                    "!" if x == self.0 => return Ok(()),
+                    "!" => return Err(JudgeError::wrong(format!("guessed {}", x))),
+                    _ => return Err(JudgeError::wrong(format!("unknown request `{}`", kind))),
+                }
+            }
+        }
+    }
+
+    /// Binary search, or a linear scan from 1 when `linear` is set.
+    struct Searcher {
+        linear: bool,
+        flush: bool,
+    }
+
+    impl Solver for Searcher {
+        fn solve<R: BufRead, W: Write>(&self, input: R, mut output: W) -> Result<(), SolveError> {
+            let mut scan = Scanner::new(input);
+            let (mut lo, mut hi) = (1, 100);
+            loop {
+                let x = if self.linear { lo } else { (lo + hi) / 2 };
+                writeln!(output, "? {}", x)?;
+                if self.flush {
+                    output.flush()?;
+                }
+                match scan.next::<String>()?.as_str() {
+                    "<" => lo = x + 1,
+                    ">" => hi = x - 1,
+                    _ => {
+                        writeln!(output, "! {}", x)?;
+                        output.flush()?;
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+
+    const BINARY: Searcher = Searcher {
+        linear: false,
+        flush: true,
+    };
+
+    #[test]
+    fn binary_search_is_accepted_with_a_transcript() {
+        let report = Interactor::new(Guess(30), BINARY).run();
+        assert_eq!(report.outcome, Outcome::Accepted);
+        assert_eq!(report.queries, 7);
+        let transcript = report.transcript.to_string();
+        assert!(transcript.starts_with("> ? 50\n< >\n> ? 25\n< <\n"));
+        assert!(transcript.ends_with("< =\n> ! 30\n"));
+    }
+
+    #[test]
+    fn query_limit_is_enforced() {
+        let mut interactor = Interactor::new(
+            Guess(30),
+            Searcher {
+                linear: true,
+                flush: true,
+            },
+        );
+        interactor.query_limit = Some(7);
+        let report = interactor.run();
+        assert_eq!(report.outcome, Outcome::QueryLimitExceeded(7));
+        assert_eq!(report.queries, 8);
+
+        interactor.solver = BINARY;
+        assert_eq!(interactor.run().outcome, Outcome::Accepted);
+    }
+
+    #[test]
+    fn a_missing_flush_is_a_deadlock() {
+        let report = Interactor::new(
+            Guess(30),
+            Searcher {
+                linear: false,
+                flush: false,
+            },
+        )
+        .run();
+        assert_eq!(report.outcome, Outcome::Deadlock);
+        assert!(report.transcript.0.is_empty());
+    }
+
+    #[test]
+    fn wrong_guesses_and_early_exits_are_rejected() {
+        struct Blurt;
+        impl Solver for Blurt {
+            fn solve<R: BufRead, W: Write>(&self, _: R, mut output: W) -> Result<(), SolveError> {
+                writeln!(output, "! 7")?;
+                Ok(())
+            }
+        }
+        struct Quit;
+        impl Solver for Quit {
+            fn solve<R: BufRead, W: Write>(&self, _: R, _: W) -> Result<(), SolveError> {
+                Ok(())
+            }
+        }
+        assert_eq!(
+            Interactor::new(Guess(30), Blurt).run().outcome,
+            Outcome::WrongAnswer("guessed 7".to_owned())
+        );
+        assert!(matches!(
+            Interactor::new(Guess(30), Quit).run().outcome,
+            Outcome::WrongAnswer(message) if message.contains("end of input")
+        ));
+    }
+}
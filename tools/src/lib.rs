@@ -0,0 +1,12 @@
+//! Local judging tools: fixture discovery, output checkers, resource limits,
+//! the sample-test runner, the stress tester, the interactor for interactive
+//! problems, the single-file bundler and the problem catalog.
+
+pub mod bundle;
+pub mod catalog;
+pub mod checker;
+pub mod fixture;
+pub mod interactor;
+pub mod limits;
+pub mod runner;
+pub mod stress;
@@ -0,0 +1,278 @@
+//! Runs a solution under judge-like limits.
+//!
+//! CPU time and address space are capped with `setrlimit` in the child, wall
+//! clock time is watched by the parent, which kills the child once it is
+//! over. Resource usage is read back from `wait4`.
+//!
+//! Per-problem limits come from an optional `limits` file in the fixture
+//! directory, one `key value` pair per line:
+//!
+//! ```text
+//! time 3       # CPU seconds
+//! wall 6       # wall clock seconds
+//! memory 256   # address space in MB
+//! ```
+
+use std::io::{self, Read};
+use std::mem;
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::process::{Command, ExitStatus, Stdio};
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    pub cpu: Duration,
+    pub wall: Duration,
+    /// Address space limit in bytes.
+    pub memory: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            cpu: Duration::from_secs(2),
+            wall: Duration::from_secs(5),
+            memory: 256 << 20,
+        }
+    }
+}
+
+impl Limits {
+    /// Applies the `key value` lines of a `limits` file on top of `self`.
+    pub fn with(mut self, spec: &str) -> Result<Self, String> {
+        for line in spec.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = line
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| format!("expected `key value`, found `{}`", line))?;
+            let value: f64 = value
+                .trim()
+                .parse()
+                .ok()
+                .filter(|v: &f64| v.is_finite() && *v > 0.)
+                .ok_or_else(|| format!("invalid {} limit `{}`", key, value.trim()))?;
+            match key {
+                "time" => self.cpu = Duration::from_secs_f64(value),
+                "wall" => self.wall = Duration::from_secs_f64(value),
+                "memory" => self.memory = (value * (1 << 20) as f64) as u64,
+                _ => return Err(format!("unknown limit `{}`", key)),
+            }
+        }
+        Ok(self)
+    }
+}
+
+impl FromStr for Limits {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Limits::default().with(s)
+    }
+}
+
+/// Outcome of one limited run.
+#[derive(Debug)]
+pub struct Execution {
+    pub status: ExitStatus,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    pub elapsed: Duration,
+    /// User plus system CPU time.
+    pub cpu: Duration,
+    /// Peak resident set size in bytes.
+    pub peak_rss: u64,
+    /// The child was killed for running past the wall clock limit.
+    pub wall_timeout: bool,
+}
+
+impl Execution {
+    pub fn time_limit_exceeded(&self, limits: &Limits) -> bool {
+        self.wall_timeout || self.cpu > limits.cpu || self.status.signal() == Some(libc::SIGXCPU)
+    }
+
+    /// Either the address space limit made an allocation fail (Rust prints
+    /// `memory allocation of N bytes failed` and aborts) or the resident set
+    /// grew past the limit anyway.
+    pub fn memory_limit_exceeded(&self, limits: &Limits) -> bool {
+        self.peak_rss > limits.memory
+            || (self.status.signal() == Some(libc::SIGABRT)
+                && String::from_utf8_lossy(&self.stderr).contains("memory allocation of"))
+    }
+}
+
+/// Runs `command`, whose stdin is already set up, under `limits`.
+pub fn execute(mut command: Command, limits: &Limits) -> io::Result<Execution> {
+    let cpu_seconds = limits.cpu.as_secs_f64().ceil() as libc::rlim_t;
+    let memory = limits.memory as libc::rlim_t;
+    command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .env("RUST_BACKTRACE", "0");
+    // SAFETY: setrlimit is async-signal-safe and the closure allocates nothing.
+    unsafe {
+        command.pre_exec(move || {
+            set_limit(libc::RLIMIT_CPU, cpu_seconds, cpu_seconds + 1)?;
+            set_limit(libc::RLIMIT_AS, memory, memory)
+        });
+    }
+
+    let start = Instant::now();
+    let mut child = command.spawn()?;
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+    let pid = child.id() as libc::pid_t;
+
+    let mut wall_timeout = false;
+    let (status, usage) = loop {
+        match wait4(pid, libc::WNOHANG)? {
+            Some(done) => break done,
+            None if start.elapsed() > limits.wall => {
+                wall_timeout = true;
+                child.kill()?;
+                if let Some(done) = wait4(pid, 0)? {
+                    break done;
+                }
+            }
+            None => thread::sleep(Duration::from_millis(1)),
+        }
+    };
+    let elapsed = start.elapsed();
+
+    Ok(Execution {
+        status: ExitStatus::from_raw(status),
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+        elapsed,
+        cpu: to_duration(usage.ru_utime) + to_duration(usage.ru_stime),
+        // ru_maxrss is in kilobytes on Linux
+        peak_rss: usage.ru_maxrss as u64 * 1024,
+        wall_timeout,
+    })
+}
+
+/// The type of the `RLIMIT_*` constants, glibc has its own.
+#[cfg(target_env = "gnu")]
+type Resource = libc::__rlimit_resource_t;
+#[cfg(not(target_env = "gnu"))]
+type Resource = libc::c_int;
+
+fn set_limit(
+    resource: Resource,
+    soft: libc::rlim_t,
+    hard: libc::rlim_t,
+) -> io::Result<()> {
+    let limit = libc::rlimit {
+        rlim_cur: soft,
+        rlim_max: hard,
+    };
+    // SAFETY: `limit` is a valid rlimit for the duration of the call.
+    if unsafe { libc::setrlimit(resource, &limit) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Reaps `pid`, `None` when `WNOHANG` was given and it is still running.
+fn wait4(pid: libc::pid_t, options: libc::c_int) -> io::Result<Option<(i32, libc::rusage)>> {
+    let mut status = 0;
+    // SAFETY: rusage is plain old data, all zeroes is a valid value.
+    let mut usage: libc::rusage = unsafe { mem::zeroed() };
+    loop {
+        // SAFETY: both out pointers are valid for writes.
+        let reaped = unsafe { libc::wait4(pid, &mut status, options, &mut usage) };
+        match reaped {
+            0 => return Ok(None),
+            -1 => {
+                let error = io::Error::last_os_error();
+                if error.kind() != io::ErrorKind::Interrupted {
+                    return Err(error);
+                }
+            }
+            _ => return Ok(Some((status, usage))),
+        }
+    }
+}
+
+fn drain<R: Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buffer);
+        }
+        buffer
+    })
+}
+
+fn to_duration(time: libc::timeval) -> Duration {
+    Duration::new(time.tv_sec as u64, time.tv_usec as u32 * 1000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_limits_files() {
+        let limits: Limits = "time 3\n# the judge gives 256 MB\nmemory 64\n"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            limits,
+            Limits {
+                cpu: Duration::from_secs(3),
+                memory: 64 << 20,
+                ..Limits::default()
+            }
+        );
+        assert_eq!(
+            "wall 0.5".parse::<Limits>().unwrap().wall,
+            Duration::from_millis(500)
+        );
+        assert!("time".parse::<Limits>().is_err());
+        assert!("time -1".parse::<Limits>().is_err());
+        assert!("stack 8".parse::<Limits>().is_err());
+    }
+
+    fn sh(script: &str, limits: &Limits) -> Execution {
+        let mut command = Command::new("/bin/sh");
+        command.args(["-c", script]).stdin(Stdio::null());
+        execute(command, limits).unwrap()
+    }
+
+    #[test]
+    fn captures_output_and_usage() {
+        let run = sh("echo hello; echo oops >&2", &Limits::default());
+        assert!(run.status.success());
+        assert_eq!(run.stdout, b"hello\n");
+        assert_eq!(run.stderr, b"oops\n");
+        assert!(run.peak_rss > 0);
+        assert!(!run.time_limit_exceeded(&Limits::default()));
+    }
+
+    #[test]
+    fn wall_clock_limit_kills_the_child() {
+        let limits = Limits {
+            wall: Duration::from_millis(200),
+            ..Limits::default()
+        };
+        let run = sh("exec sleep 5", &limits);
+        assert!(run.wall_timeout);
+        assert!(run.time_limit_exceeded(&limits));
+        assert!(run.elapsed < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn allocation_failures_abort() {
+        let limits = Limits::default();
+        let printed = sh("echo 'memory allocation of 8 bytes failed' >&2; exit 101", &limits);
+        assert!(!printed.memory_limit_exceeded(&limits));
+        let aborted = sh("echo 'memory allocation of 8 bytes failed' >&2; kill -ABRT $$", &limits);
+        assert!(aborted.memory_limit_exceeded(&limits));
+    }
+}
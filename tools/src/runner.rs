@@ -0,0 +1,143 @@
+//! Runs a built solution binary on a fixture and judges its output.
+
+use crate::checker::{Checker, Deviation, Mismatch};
+use crate::fixture::Fixture;
+use crate::limits::{self, Limits};
+use std::fmt;
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+use std::process::{Command, ExitStatus};
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Ok,
+    WrongAnswer(Mismatch),
+    /// The solution crashed; holds its panic or error message.
+    RuntimeError(String),
+    TimeLimitExceeded,
+    MemoryLimitExceeded,
+}
+
+impl Verdict {
+    pub fn is_ok(&self) -> bool {
+        matches!(self, Verdict::Ok)
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Verdict::Ok => f.write_str("OK"),
+            Verdict::WrongAnswer(mismatch) => write!(f, "WA {}", mismatch),
+            Verdict::RuntimeError(message) => write!(f, "RE {}", message),
+            Verdict::TimeLimitExceeded => f.write_str("TLE"),
+            Verdict::MemoryLimitExceeded => f.write_str("MLE"),
+        }
+    }
+}
+
+/// Verdict of one fixture with the resources it took, and the worst numeric
+/// error when the checker compared numbers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaseReport {
+    pub verdict: Verdict,
+    pub elapsed: Duration,
+    /// Peak resident set size in bytes.
+    pub peak_rss: u64,
+    pub worst: Option<Deviation>,
+}
+
+impl fmt::Display for CaseReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({} ms, {:.1} MB)",
+            self.verdict,
+            self.elapsed.as_millis(),
+            self.peak_rss as f64 / (1 << 20) as f64
+        )?;
+        if let Some(worst) = &self.worst {
+            write!(f, ", {}", worst)?;
+        }
+        Ok(())
+    }
+}
+
+/// Feeds `fixture.input` to `exe` under `limits` and judges what it prints
+/// with `checker`.
+pub fn run(
+    exe: &Path,
+    fixture: &Fixture,
+    checker: &Checker,
+    limits: &Limits,
+) -> io::Result<CaseReport> {
+    let mut command = Command::new(exe);
+    command.stdin(File::open(&fixture.input)?);
+    let execution = limits::execute(command, limits)?;
+    let mut report = CaseReport {
+        verdict: Verdict::Ok,
+        elapsed: execution.elapsed,
+        peak_rss: execution.peak_rss,
+        worst: None,
+    };
+    report.verdict = if execution.time_limit_exceeded(limits) {
+        Verdict::TimeLimitExceeded
+    } else if execution.memory_limit_exceeded(limits) {
+        Verdict::MemoryLimitExceeded
+    } else if !execution.status.success() {
+        let stderr = String::from_utf8_lossy(&execution.stderr);
+        Verdict::RuntimeError(crash_message(execution.status, &stderr))
+    } else {
+        let expected = fs::read_to_string(&fixture.answer)?;
+        let found = String::from_utf8_lossy(&execution.stdout);
+        let check = checker.check(&expected, &found);
+        report.worst = check.worst;
+        match check.mismatch {
+            None => Verdict::Ok,
+            Some(mismatch) => Verdict::WrongAnswer(mismatch),
+        }
+    };
+    Ok(report)
+}
+
+/// Extracts the panic message, or the `Error: ..` line printed when `main`
+/// returns an error, from a crashed process' stderr.
+pub fn crash_message(status: ExitStatus, stderr: &str) -> String {
+    let mut lines = stderr.lines();
+    while let Some(line) = lines.next() {
+        if let Some(at) = line.find("panicked at ") {
+            let location = line[at + "panicked at ".len()..].trim_end_matches(':');
+            // since Rust 1.73 the message is on the lines following the location
+            let message: Vec<&str> = lines
+                .by_ref()
+                .take_while(|l| !l.starts_with("note: ") && !l.starts_with("stack backtrace:"))
+                .collect();
+            return format!("panicked at {}: {}", location, message.join(" "));
+        }
+        if let Some(error) = line.strip_prefix("Error: ") {
+            return error.to_owned();
+        }
+    }
+    status.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panic_message_is_extracted() {
+        let stderr = "\nthread 'main' panicked at codeforces/2.rs:270:38:\nfailed to parse n: ParseIntError { kind: InvalidDigit }\nnote: run with `RUST_BACKTRACE=1` environment variable to display a backtrace\n";
+        let status = Command::new("false").status().unwrap();
+        assert_eq!(
+            crash_message(status, stderr),
+            "panicked at codeforces/2.rs:270:38: failed to parse n: ParseIntError { kind: InvalidDigit }"
+        );
+        assert_eq!(
+            crash_message(status, "Error: Scan(ScanError { line: 1 })\n"),
+            "Scan(ScanError { line: 1 })"
+        );
+    }
+}
@@ -0,0 +1,308 @@
+//! Stress testing: random inputs are fed to a trusted (usually brute-force)
+//! oracle and to the candidate solution until their answers differ, then the
+//! failing input is shrunk by deleting tokens while the two still disagree.
+
+use crate::checker::Checker;
+use common::solver::Solver;
+use std::any::Any;
+use std::cell::Cell;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Once;
+
+/// Small deterministic generator (splitmix64), so a seed always replays the
+/// same sequence of inputs.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `lo..=hi`.
+    pub fn range(&mut self, lo: i64, hi: i64) -> i64 {
+        assert!(lo <= hi, "empty range {}..={}", lo, hi);
+        let span = (hi as i128 - lo as i128 + 1) as u128;
+        (lo as i128 + (self.next_u64() as u128 % span) as i128) as i64
+    }
+
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.range(0, items.len() as i64 - 1) as usize]
+    }
+}
+
+/// Produces one random test file per call.
+pub trait Generator {
+    fn generate(&self, rng: &mut Rng) -> String;
+}
+
+impl<F: Fn(&mut Rng) -> String> Generator for F {
+    fn generate(&self, rng: &mut Rng) -> String {
+        self(rng)
+    }
+}
+
+/// An input on which the oracle and the candidate disagree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Counterexample {
+    pub seed: u64,
+    /// 0-based iteration that produced `input`.
+    pub iteration: usize,
+    pub input: String,
+    /// `input` after shrinking.
+    pub shrunk: String,
+    /// Oracle and candidate answers on `shrunk`; `Err` holds an error or
+    /// panic message.
+    pub expected: Result<String, String>,
+    pub found: Result<String, String>,
+}
+
+impl fmt::Display for Counterexample {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "oracle and candidate disagree (seed {}, iteration {})",
+            self.seed, self.iteration
+        )?;
+        writeln!(f, "--- input ---\n{}", self.shrunk.trim_end())?;
+        writeln!(f, "--- oracle ---\n{}", show(&self.expected))?;
+        write!(f, "--- candidate ---\n{}", show(&self.found))
+    }
+}
+
+fn show(answer: &Result<String, String>) -> &str {
+    match answer {
+        Ok(output) => output.trim_end(),
+        Err(message) => message,
+    }
+}
+
+pub struct Stress<G, O, C> {
+    pub generator: G,
+    pub oracle: O,
+    pub candidate: C,
+    pub checker: Checker,
+}
+
+impl<G: Generator, O: Solver, C: Solver> Stress<G, O, C> {
+    pub fn new(generator: G, oracle: O, candidate: C) -> Self {
+        Stress {
+            generator,
+            oracle,
+            candidate,
+            checker: Checker::Exact,
+        }
+    }
+
+    /// Runs `iterations` random inputs from `seed` and stops at the first
+    /// disagreement.
+    pub fn run(&self, seed: u64, iterations: usize) -> Result<(), Box<Counterexample>> {
+        let mut rng = Rng::new(seed);
+        for iteration in 0..iterations {
+            let input = self.generator.generate(&mut rng);
+            if self.disagree(&input) {
+                let shrunk = self.shrink(&input);
+                return Err(Box::new(Counterexample {
+                    seed,
+                    iteration,
+                    input,
+                    expected: answer(&self.oracle, &shrunk),
+                    found: answer(&self.candidate, &shrunk),
+                    shrunk,
+                }));
+            }
+        }
+        Ok(())
+    }
+
+    /// Inputs both solvers reject count as agreement: they are not valid
+    /// tests, and shrinking easily produces them.
+    fn disagree(&self, input: &str) -> bool {
+        match (answer(&self.oracle, input), answer(&self.candidate, input)) {
+            (Ok(expected), Ok(found)) => self.checker.check(&expected, &found).mismatch.is_some(),
+            (Err(_), Err(_)) => false,
+            _ => true,
+        }
+    }
+
+    /// Greedily deletes whole lines, then single tokens, as long as the
+    /// solvers keep disagreeing, until no single deletion does.
+    fn shrink(&self, input: &str) -> String {
+        let mut lines: Vec<Vec<String>> = input
+            .lines()
+            .map(|line| line.split_whitespace().map(str::to_owned).collect())
+            .collect();
+        loop {
+            let mut progress = false;
+            let mut i = 0;
+            while i < lines.len() {
+                let removed = lines.remove(i);
+                if self.disagree(&render(&lines)) {
+                    progress = true;
+                } else {
+                    lines.insert(i, removed);
+                    i += 1;
+                }
+            }
+            for line in 0..lines.len() {
+                let mut j = 0;
+                while j < lines[line].len() {
+                    let removed = lines[line].remove(j);
+                    if self.disagree(&render(&lines)) {
+                        progress = true;
+                    } else {
+                        lines[line].insert(j, removed);
+                        j += 1;
+                    }
+                }
+            }
+            if !progress {
+                return render(&lines);
+            }
+        }
+    }
+}
+
+fn render(lines: &[Vec<String>]) -> String {
+    lines.iter().map(|line| line.join(" ") + "\n").collect()
+}
+
+thread_local! {
+    /// Set while [`answer`] runs a solver on this thread, whose panics are
+    /// expected and reported as answers instead.
+    static SILENCED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Wraps the process-wide panic hook, once, so that it stays quiet on
+/// silenced threads. The hook is shared by every thread, swapping it around
+/// each call would race with parallel tests.
+fn install_silencing_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !SILENCED.with(Cell::get) {
+                previous(info);
+            }
+        }));
+    });
+}
+
+/// Output of `solver` on `input`, turning errors and panics into `Err`.
+fn answer<S: Solver>(solver: &S, input: &str) -> Result<String, String> {
+    install_silencing_hook();
+    let silenced = SILENCED.with(|flag| flag.replace(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(|| solver.solve_bytes(input.as_bytes())));
+    SILENCED.with(|flag| flag.set(silenced));
+    match result {
+        Ok(Ok(output)) => Ok(String::from_utf8_lossy(&output).into_owned()),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(payload) => Err(format!("panicked: {}", panic_message(&*payload))),
+    }
+}
+
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s
+    } else {
+        "non-string panic payload"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::solver::SolveError;
+    use std::io::{BufRead, Write};
+
+    /// Sums every number of the input, `skip` being ignored.
+    struct Sum {
+        skip: Option<i64>,
+    }
+
+    impl Solver for Sum {
+        fn solve<R: BufRead, W: Write>(
+            &self,
+            mut input: R,
+            mut output: W,
+        ) -> Result<(), SolveError> {
+            let mut text = String::new();
+            input.read_to_string(&mut text)?;
+            let total: i64 = text
+                .split_whitespace()
+                .map(|t| t.parse::<i64>().unwrap())
+                .filter(|&n| Some(n) != self.skip)
+                .sum();
+            writeln!(output, "{}", total)?;
+            Ok(())
+        }
+    }
+
+    fn numbers(rng: &mut Rng) -> String {
+        let rows: Vec<String> = (0..rng.range(1, 5))
+            .map(|_| {
+                let row: Vec<String> = (0..rng.range(1, 6))
+                    .map(|_| rng.range(0, 9).to_string())
+                    .collect();
+                row.join(" ")
+            })
+            .collect();
+        rows.join("\n") + "\n"
+    }
+
+    #[test]
+    fn rng_is_deterministic_and_in_range() {
+        let (mut a, mut b) = (Rng::new(7), Rng::new(7));
+        for _ in 0..1000 {
+            let x = a.range(-3, 3);
+            assert_eq!(x, b.range(-3, 3));
+            assert!((-3..=3).contains(&x));
+        }
+        assert_eq!(
+            Rng::new(1).range(i64::MIN, i64::MAX),
+            Rng::new(1).range(i64::MIN, i64::MAX)
+        );
+    }
+
+    #[test]
+    fn agreeing_solvers_pass() {
+        let stress = Stress::new(numbers, Sum { skip: None }, Sum { skip: None });
+        assert_eq!(stress.run(1, 200), Ok(()));
+    }
+
+    #[test]
+    fn disagreement_is_shrunk_to_one_token() {
+        let stress = Stress::new(numbers, Sum { skip: None }, Sum { skip: Some(7) });
+        let found = stress.run(1, 200).unwrap_err();
+        assert_eq!(found.shrunk, "7\n");
+        assert_eq!(found.expected, Ok("7\n".to_owned()));
+        assert_eq!(found.found, Ok("0\n".to_owned()));
+        assert!(found.to_string().contains("--- input ---\n7\n"));
+    }
+
+    #[test]
+    fn a_panicking_candidate_disagrees() {
+        struct Panics;
+        impl Solver for Panics {
+            fn solve<R: BufRead, W: Write>(&self, _: R, _: W) -> Result<(), SolveError> {
+                panic!("boom")
+            }
+        }
+        let found = Stress::new(numbers, Sum { skip: None }, Panics)
+            .run(3, 1)
+            .unwrap_err();
+        assert_eq!(found.found, Err("panicked: boom".to_owned()));
+        assert!(!SILENCED.with(Cell::get));
+    }
+}